@@ -0,0 +1,59 @@
+use super::bisync;
+
+use crate::{Bandwidth, I16x3, Measurements, Odr, Scale, Status};
+
+/// Functionality shared by the SPI and I2C drivers
+///
+/// Allows application code to be generic over the bus the sensor is
+/// connected to. The methods behave like the inherent methods of the same
+/// name.
+#[allow(async_fn_in_trait)]
+#[bisync]
+pub trait Gyroscope {
+    /// Error of the underlying bus
+    type Error;
+
+    /// Gyroscope measurements
+    #[bisync]
+    async fn gyro(&mut self) -> Result<I16x3, Self::Error>;
+
+    /// Temperature measurement + gyroscope measurements
+    #[bisync]
+    async fn all(&mut self) -> Result<Measurements, Self::Error>;
+
+    /// Raw temperature sensor measurement
+    #[bisync]
+    async fn temp_raw(&mut self) -> Result<i8, Self::Error>;
+
+    /// Read the status register
+    #[bisync]
+    async fn status(&mut self) -> Result<Status, Self::Error>;
+
+    /// Reads the WHO_AM_I register
+    #[bisync]
+    async fn who_am_i(&mut self) -> Result<u8, Self::Error>;
+
+    /// Get the current Output Data Rate
+    #[bisync]
+    async fn odr(&mut self) -> Result<Odr, Self::Error>;
+
+    /// Set the Output Data Rate
+    #[bisync]
+    async fn set_odr(&mut self, odr: Odr) -> Result<&mut Self, Self::Error>;
+
+    /// Get the current Bandwidth
+    #[bisync]
+    async fn bandwidth(&mut self) -> Result<Bandwidth, Self::Error>;
+
+    /// Set the low-pass cut-off frequency (i.e. bandwidth)
+    #[bisync]
+    async fn set_bandwidth(&mut self, bw: Bandwidth) -> Result<&mut Self, Self::Error>;
+
+    /// Get the current Full Scale Selection
+    #[bisync]
+    async fn scale(&mut self) -> Result<Scale, Self::Error>;
+
+    /// Set the Full Scale Selection
+    #[bisync]
+    async fn set_scale(&mut self, scale: Scale) -> Result<&mut Self, Self::Error>;
+}
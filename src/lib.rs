@@ -13,11 +13,16 @@
 #![deny(warnings)]
 #![no_std]
 
+#[cfg(test)]
+extern crate std;
+
 #[path = "."]
 #[allow(clippy::duplicate_mod)]
 /// Asynchronous support.
 pub mod asynchronous {
     use bisync::asynchronous::*;
+    /// Bus independent driver trait.
+    pub mod gyroscope;
     /// I2C module.
     pub mod i2c;
     /// SPI module.
@@ -29,6 +34,8 @@ pub mod asynchronous {
 /// Blocking support.
 pub mod blocking {
     use bisync::synchronous::*;
+    /// Bus independent driver trait.
+    pub mod gyroscope;
     /// I2C module.
     pub mod i2c;
     /// SPI module.
@@ -38,6 +45,22 @@ pub mod blocking {
 /// Re-export the blocking module as the default.
 pub use blocking::*;
 
+/// Number of measurements averaged by `verify_scale`
+pub const SCALE_VERIFICATION_SAMPLES: u16 = 32;
+
+/// X-axis bit of the `gyro_axes` selection mask
+pub const AXES_X: u8 = 1;
+/// Y-axis bit of the `gyro_axes` selection mask
+pub const AXES_Y: u8 = 1 << 1;
+/// Z-axis bit of the `gyro_axes` selection mask
+pub const AXES_Z: u8 = 1 << 2;
+
+/// Minimal rate in degrees per second for `dominant_axis` to report an axis
+pub const DOMINANT_AXIS_THRESHOLD_DPS: f32 = 5.0;
+
+/// Marker returned by `gyro_delta_encoded` for deltas outside of `-127..=127`
+pub const DELTA_OVERFLOW: i8 = i8::MIN;
+
 /// Minimal time in nanoseconds between chip select assertion and clock edge.
 pub const MINIMUM_CS_SETUP_TIME_NS: u32 = 5;
 
@@ -46,6 +69,165 @@ pub const WHO_AM_I_L3GD20: u8 = 0xD4;
 /// Expected WHO_AM_I register value for the L3GD20H sensor.
 pub const WHO_AM_I_L3GD20H: u8 = 0xD7;
 
+/// Maximal number of DRDY pin polls `gyro_guaranteed_fresh` waits for each
+/// data-ready signal, and of `STATUS_REG` polls `wait_for_data` waits for new
+/// data
+pub const DATA_READY_POLLS: u32 = 10_000;
+
+/// Time in microseconds after which `measure_startup_time` gives up waiting
+/// for the first sample
+pub const STARTUP_TIMEOUT_US: u32 = 1_000_000;
+
+/// Time in microseconds `calibrate_scale_two_point` waits for the turntable
+/// to reverse from the positive to the negative rate
+pub const TURNTABLE_REVERSAL_TIMEOUT_US: u32 = 60_000_000;
+
+/// Maximal number of `STATUS_REG` polls `force_interrupt_test` waits for two
+/// new samples
+pub const INTERRUPT_TEST_POLLS: u32 = 10_000;
+
+/// Driver error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    /// Error of the underlying bus
+    Bus(E),
+    /// Raw I2C address is neither `0x6A` nor `0x6B`
+    InvalidAddress,
+    /// Error reading a GPIO pin
+    Pin,
+    /// Expected event did not occur in time
+    Timeout,
+    /// `WHO_AM_I` register does not match a known sensor, contains the read value
+    InvalidWhoAmI(u8),
+    /// `WHO_AM_I` register does not match the value expected by
+    /// `expect_who_am_i`, contains the read value
+    WrongDevice(u8),
+    /// Setting key not recognized by `apply_setting`
+    UnknownSetting,
+    /// Value not valid for the given setting key
+    BadValue,
+    /// Feature not supported by the detected sensor model
+    Unsupported,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::Bus(e)
+    }
+}
+
+/// Monotonic clock used for timing measurements
+pub trait SampleClock {
+    /// Current time in microseconds
+    ///
+    /// The value is allowed to wrap around.
+    fn now_us(&mut self) -> u32;
+}
+
+/// Integration strategy for angular rates
+pub trait Integrator {
+    /// Accumulate the angular rate `rate` in degrees per second over `dt`
+    /// seconds
+    fn accumulate(&mut self, rate: F32x3, dt: f32);
+}
+
+/// Saved configuration of the sensor
+///
+/// Created by `save_state` and written back by `restore_state`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceState {
+    ctrl_reg1: u8,
+    registers: [u8; STATE_REGISTERS.len()],
+    axes: u8,
+}
+
+impl DeviceState {
+    /// Whether `REFERENCE` is part of a state saved with `CTRL_REG2` set to
+    /// `reg2`, see `save_state`
+    fn saves_reference(reg2: u8) -> bool {
+        HighPassMode::from_u8(reg2) != HighPassMode::NormalReset
+    }
+}
+
+/// Sensor model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Model {
+    /// L3GD20
+    L3gd20,
+    /// L3GD20H
+    L3gd20h,
+}
+
+impl Model {
+    /// Detect the model from the `WHO_AM_I` register value
+    pub fn from_who_am_i(who_am_i: u8) -> Option<Self> {
+        match who_am_i {
+            WHO_AM_I_L3GD20 => Some(Model::L3gd20),
+            WHO_AM_I_L3GD20H => Some(Model::L3gd20h),
+            _ => None,
+        }
+    }
+
+    /// Capabilities of the model
+    pub fn capabilities(&self) -> Capabilities {
+        match *self {
+            Model::L3gd20 => Capabilities {
+                model: *self,
+                odrs_hz: &[95.0, 190.0, 380.0, 760.0],
+                scales: &[Scale::Dps250, Scale::Dps500, Scale::Dps2000],
+                fifo_depth: FIFO_DEPTH,
+                low_odr: false,
+            },
+            Model::L3gd20h => Capabilities {
+                model: *self,
+                odrs_hz: &[12.5, 25.0, 50.0, 100.0, 200.0, 400.0, 800.0],
+                scales: &[Scale::Dps250, Scale::Dps500, Scale::Dps2000],
+                fifo_depth: FIFO_DEPTH,
+                low_odr: true,
+            },
+        }
+    }
+}
+
+/// Capabilities of a sensor model
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capabilities {
+    /// Sensor model
+    pub model: Model,
+    /// Supported output data rates in Hz
+    pub odrs_hz: &'static [f32],
+    /// Supported full scale selections
+    pub scales: &'static [Scale],
+    /// Depth of the FIFO in samples
+    pub fifo_depth: usize,
+    /// Whether the low output data rates are supported
+    pub low_odr: bool,
+}
+
+/// Future which returns `Pending` once, giving other tasks a chance to run
+#[derive(Default)]
+struct YieldNow(bool);
+
+impl core::future::Future for YieldNow {
+    type Output = ();
+
+    fn poll(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<()> {
+        if self.0 {
+            return core::task::Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        core::task::Poll::Pending
+    }
+}
+
 /// Trait to represent a value that can be sent to sensor
 pub trait BitValue {
     /// The width of the bitfield in bits
@@ -63,6 +245,7 @@ pub trait BitValue {
     fn value(&self) -> u8;
 }
 
+/// Register addresses, for use with `read_register` and `write_register`
 #[allow(dead_code)]
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
@@ -95,6 +278,121 @@ pub enum Register {
     INT1_TSH_ZH = 0x36,
     INT1_TSH_ZL = 0x37,
     INT1_DURATION = 0x38,
+    /// L3GD20H only
+    LOW_ODR = 0x39,
+}
+
+/// How multi-byte measurements are read from the sensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReadMode {
+    /// Single burst transfer using register address auto-increment
+    #[default]
+    Burst,
+    /// One transfer per register
+    ///
+    /// Workaround for bus controllers which corrupt long bursts.
+    SingleByte,
+}
+
+/// Power mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerMode {
+    /// Powered down
+    PowerDown,
+    /// Powered up with all axes disabled, allows a fast wake-up
+    Sleep,
+    /// Powered up with all axes enabled
+    Normal,
+}
+
+impl PowerMode {
+    /// Typical supply current in µA of `model` according to its data sheet
+    ///
+    /// The data sheets specify a single typical value per power mode, with no
+    /// dependence on the output data rate, so the rate does not change the
+    /// result. The L3GD20H draws less than the L3GD20 in normal and
+    /// power-down mode, but more in sleep mode.
+    pub fn typical_current_ua(&self, model: Model) -> u32 {
+        match (model, *self) {
+            (Model::L3gd20, PowerMode::PowerDown) => 5,
+            (Model::L3gd20, PowerMode::Sleep) => 2_000,
+            (Model::L3gd20, PowerMode::Normal) => 6_100,
+            (Model::L3gd20h, PowerMode::PowerDown) => 1,
+            (Model::L3gd20h, PowerMode::Sleep) => 2_500,
+            (Model::L3gd20h, PowerMode::Normal) => 5_000,
+        }
+    }
+
+    fn from_u8(from: u8) -> Self {
+        // Decode `PD` and `Xen-Zen` bits of `CTRL_REG1`
+        if from & CTRL_REG1_PD == 0 {
+            PowerMode::PowerDown
+        } else if from & CTRL_REG1_AXES == 0 {
+            PowerMode::Sleep
+        } else {
+            PowerMode::Normal
+        }
+    }
+
+    /// `PD` and axis enable bits of `CTRL_REG1`, `axes` are the enabled axes
+    /// in normal mode
+    fn to_u8(self, axes: u8) -> u8 {
+        match self {
+            PowerMode::PowerDown => 0,
+            PowerMode::Sleep => CTRL_REG1_PD,
+            PowerMode::Normal => CTRL_REG1_PD | (axes & CTRL_REG1_AXES),
+        }
+    }
+}
+
+/// Low output data rates of the L3GD20H
+///
+/// Selected with the `DR1-DR0` bits of `CTRL_REG1` while the `Low_ODR` bit
+/// of the `LOW_ODR` register is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LowOdr {
+    /// 12.5 Hz data rate
+    Hz12_5 = 0x00,
+    /// 25 Hz data rate
+    Hz25 = 0x01,
+    /// 50 Hz data rate
+    Hz50 = 0x02,
+}
+
+impl BitValue for LowOdr {
+    fn width() -> u8 {
+        2
+    }
+    fn shift() -> u8 {
+        6
+    }
+    fn value(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl LowOdr {
+    /// Nominal output data rate in Hz
+    pub fn hz(&self) -> f32 {
+        match *self {
+            LowOdr::Hz12_5 => 12.5,
+            LowOdr::Hz25 => 25.0,
+            LowOdr::Hz50 => 50.0,
+        }
+    }
+
+    fn from_u8(from: u8) -> Self {
+        // Extract ODR value, converting to enum (ROI: 0b1100_0000)
+        match (from >> LowOdr::shift()) & LowOdr::mask() {
+            x if x == LowOdr::Hz12_5 as u8 => LowOdr::Hz12_5,
+            x if x == LowOdr::Hz25 as u8 => LowOdr::Hz25,
+            // Both values with `DR1` set select 50 Hz
+            _ => LowOdr::Hz50,
+        }
+    }
 }
 
 /// Output Data Rate
@@ -124,6 +422,40 @@ impl BitValue for Odr {
 }
 
 impl Odr {
+    /// Nominal output data rate in Hz
+    pub fn hz(&self) -> f32 {
+        match *self {
+            Odr::Hz95 => 95.0,
+            Odr::Hz190 => 190.0,
+            Odr::Hz380 => 380.0,
+            Odr::Hz760 => 760.0,
+        }
+    }
+
+    /// Parse the nominal rate in Hz, e.g. `"95"`
+    fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "95" => Some(Odr::Hz95),
+            "190" => Some(Odr::Hz190),
+            "380" => Some(Odr::Hz380),
+            "760" => Some(Odr::Hz760),
+            _ => None,
+        }
+    }
+
+    /// Duration of one output data period in microseconds
+    pub fn period_us(&self) -> u32 {
+        (1_000_000.0 / self.hz()) as u32
+    }
+
+    /// Number of output data periods closest to `ms` milliseconds
+    ///
+    /// Saturates at 127, the largest value of the `INT1_DURATION` counter.
+    pub fn duration_samples(&self, ms: u16) -> u8 {
+        let samples = ms as f32 * self.hz() / 1000.0 + 0.5;
+        (samples as u32).min(INT1_DURATION_D as u32) as u8
+    }
+
     fn from_u8(from: u8) -> Self {
         // Extract ODR value, converting to enum (ROI: 0b1100_0000)
         match (from >> Odr::shift()) & Odr::mask() {
@@ -161,6 +493,16 @@ impl BitValue for Scale {
 }
 
 impl Scale {
+    /// Parse the full scale in degrees per second, e.g. `"250"`
+    fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "250" => Some(Scale::Dps250),
+            "500" => Some(Scale::Dps500),
+            "2000" => Some(Scale::Dps2000),
+            _ => None,
+        }
+    }
+
     fn from_u8(from: u8) -> Self {
         // Extract scale value from register, ensure that we mask with
         // `0b0000_0011` to extract `FS1-FS2` part of register
@@ -206,6 +548,37 @@ impl BitValue for Bandwidth {
 }
 
 impl Bandwidth {
+    /// Low-pass cut-off frequency in Hz at the given output data rate
+    pub fn cutoff_hz(&self, odr: Odr) -> f32 {
+        match (odr, *self) {
+            (Odr::Hz95, Bandwidth::Low) => 12.5,
+            (Odr::Hz95, _) => 25.0,
+            (Odr::Hz190, Bandwidth::Low) => 12.5,
+            (Odr::Hz190, Bandwidth::Medium) => 25.0,
+            (Odr::Hz190, Bandwidth::High) => 50.0,
+            (Odr::Hz190, Bandwidth::Maximum) => 70.0,
+            (Odr::Hz380, Bandwidth::Low) => 20.0,
+            (Odr::Hz380, Bandwidth::Medium) => 25.0,
+            (Odr::Hz380, Bandwidth::High) => 50.0,
+            (Odr::Hz380, Bandwidth::Maximum) => 100.0,
+            (Odr::Hz760, Bandwidth::Low) => 30.0,
+            (Odr::Hz760, Bandwidth::Medium) => 35.0,
+            (Odr::Hz760, Bandwidth::High) => 50.0,
+            (Odr::Hz760, Bandwidth::Maximum) => 100.0,
+        }
+    }
+
+    /// Parse the lower case variant name, e.g. `"low"`
+    fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(Bandwidth::Low),
+            "medium" => Some(Bandwidth::Medium),
+            "high" => Some(Bandwidth::High),
+            "maximum" => Some(Bandwidth::Maximum),
+            _ => None,
+        }
+    }
+
     fn from_u8(from: u8) -> Self {
         // Shift and mask bandwidth of register, (ROI: 0b0011_0000)
         match (from >> Bandwidth::shift()) & Bandwidth::mask() {
@@ -218,93 +591,1604 @@ impl Bandwidth {
     }
 }
 
-impl Register {
-    fn addr(self) -> u8 {
-        self as u8
+/// Self-test mode
+///
+/// In self-test mode an electrostatic force is applied to the sensing
+/// element, which offsets the output by a known amount. See the data sheet
+/// for the acceptance range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SelfTest {
+    /// Normal mode
+    Disabled = 0x00,
+    /// Self-test 0 (positive sign)
+    Mode0 = 0x01,
+    /// Self-test 1 (negative sign)
+    Mode1 = 0x03,
+}
+
+impl BitValue for SelfTest {
+    fn width() -> u8 {
+        2
+    }
+    fn shift() -> u8 {
+        1
+    }
+    fn value(&self) -> u8 {
+        *self as u8
     }
 }
 
-impl Scale {
-    /// Convert a measurement to degrees
-    pub fn degrees(&self, val: i16) -> f32 {
-        match *self {
-            Scale::Dps250 => val as f32 * 0.00875,
-            Scale::Dps500 => val as f32 * 0.0175,
-            Scale::Dps2000 => val as f32 * 0.07,
+/// High-pass filter mode (`HPM1-HPM0` of `CTRL_REG2`)
+///
+/// The filter only affects the output if it is enabled in `CTRL_REG5`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HighPassMode {
+    /// Normal mode, reset by reading the `REFERENCE` register
+    NormalReset = 0x00,
+    /// Reference signal for filtering, i.e. the output is the difference to
+    /// the `REFERENCE` register
+    Reference = 0x01,
+    /// Normal mode
+    Normal = 0x02,
+    /// Autoreset on interrupt event
+    AutoReset = 0x03,
+}
+
+impl BitValue for HighPassMode {
+    fn width() -> u8 {
+        2
+    }
+    fn shift() -> u8 {
+        4
+    }
+    fn value(&self) -> u8 {
+        *self as u8
+    }
+}
+
+impl HighPassMode {
+    fn from_u8(from: u8) -> Self {
+        // Extract high-pass mode, (ROI: 0b0011_0000)
+        match (from >> HighPassMode::shift()) & HighPassMode::mask() {
+            x if x == HighPassMode::NormalReset as u8 => HighPassMode::NormalReset,
+            x if x == HighPassMode::Reference as u8 => HighPassMode::Reference,
+            x if x == HighPassMode::Normal as u8 => HighPassMode::Normal,
+            x if x == HighPassMode::AutoReset as u8 => HighPassMode::AutoReset,
+            _ => unreachable!(),
         }
     }
+}
 
-    /// Convert a measurement to radians
-    pub fn radians(&self, val: i16) -> f32 {
-        // TODO: Use `to_radians` or other built in method
-        // NOTE: `to_radians` is only exported in `std` (07.02.18)
-        self.degrees(val) * (core::f32::consts::PI / 180.0)
+/// Output path selection (`Out_Sel1-Out_Sel0` of `CTRL_REG5`)
+///
+/// Selects the data written to the output registers and the FIFO. The
+/// high-pass filter must also be enabled for `HighPass` and
+/// `LowPassThenHighPass` to take effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OutSelect {
+    /// Output of the first low-pass filter only
+    Unfiltered = 0x00,
+    /// Output of the high-pass filter
+    HighPass = 0x01,
+    /// Output of the high-pass filter followed by the second low-pass filter
+    /// set by `Bandwidth`
+    LowPassThenHighPass = 0x02,
+}
+
+impl BitValue for OutSelect {
+    fn width() -> u8 {
+        2
+    }
+    fn shift() -> u8 {
+        0
+    }
+    fn value(&self) -> u8 {
+        *self as u8
     }
 }
 
-/// XYZ triple
-#[derive(Debug, Clone, Copy)]
+impl OutSelect {
+    fn from_u8(from: u8) -> Self {
+        // Extract output selection, (ROI: 0b0000_0011)
+        match (from >> OutSelect::shift()) & OutSelect::mask() {
+            x if x == OutSelect::Unfiltered as u8 => OutSelect::Unfiltered,
+            x if x == OutSelect::HighPass as u8 => OutSelect::HighPass,
+            // Both values with `Out_Sel1` set select the same path
+            _ => OutSelect::LowPassThenHighPass,
+        }
+    }
+}
+
+/// High-pass filter cutoff configuration (`HPCF3-HPCF0` of `CTRL_REG2`)
+///
+/// The cutoff frequency depends on the output data rate, see the data sheet
+/// for the table. Lower values result in a higher cutoff frequency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct I16x3 {
-    /// X component
-    pub x: i16,
-    /// Y component
-    pub y: i16,
-    /// Z component
-    pub z: i16,
+pub struct HighPassCutoff(u8);
+
+impl HighPassCutoff {
+    /// Highest valid cutoff configuration
+    pub const MAX: u8 = 9;
+
+    /// Create a cutoff configuration, or `None` if `value` is above 9
+    pub fn new(value: u8) -> Option<Self> {
+        (value <= Self::MAX).then_some(HighPassCutoff(value))
+    }
+
+    fn from_u8(from: u8) -> Self {
+        // Extract cutoff, (ROI: 0b0000_1111), values above 9 are reserved
+        HighPassCutoff(((from >> HighPassCutoff::shift()) & HighPassCutoff::mask()).min(Self::MAX))
+    }
 }
 
-/// Several measurements
-#[derive(Debug, Clone, Copy)]
+impl BitValue for HighPassCutoff {
+    fn width() -> u8 {
+        4
+    }
+    fn shift() -> u8 {
+        0
+    }
+    fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+/// FIFO mode
+///
+/// The FIFO holds up to `FIFO_DEPTH` samples. See the data sheet for the
+/// behaviour of the individual modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Measurements {
-    /// Gyroscope measurements
-    pub gyro: I16x3,
-    /// Raw temperature sensor measurement
-    pub temp_raw: i8,
+pub enum FifoMode {
+    /// FIFO not used, the output registers hold the latest sample
+    Bypass = 0x00,
+    /// Collect samples until the FIFO is full, then stop
+    Fifo = 0x01,
+    /// Collect samples, overwriting the oldest one when the FIFO is full
+    Stream = 0x02,
+    /// Stream mode until an interrupt event, then FIFO mode
+    StreamToFifo = 0x03,
+    /// Bypass mode until an interrupt event, then Stream mode
+    BypassToStream = 0x04,
+    /// Stream mode emptying the FIFO down to the watermark level on each
+    /// read, only available on the L3GD20H
+    DynamicStream = 0x06,
+    /// Bypass mode until an interrupt event, then FIFO mode, only available
+    /// on the L3GD20H
+    BypassToFifo = 0x07,
 }
 
-impl Measurements {
-    /// Convert the raw temperature value to degrees celcius
-    pub fn temp_celcius(&self) -> i16 {
-        25 - self.temp_raw as i16
+impl BitValue for FifoMode {
+    fn width() -> u8 {
+        3
+    }
+    fn shift() -> u8 {
+        5
+    }
+    fn value(&self) -> u8 {
+        *self as u8
     }
 }
 
-/// Sensor status
+impl FifoMode {
+    fn from_u8(from: u8) -> Self {
+        // Extract FIFO mode, (ROI: 0b1110_0000)
+        match (from >> FifoMode::shift()) & FifoMode::mask() {
+            x if x == FifoMode::Fifo as u8 => FifoMode::Fifo,
+            x if x == FifoMode::Stream as u8 => FifoMode::Stream,
+            x if x == FifoMode::StreamToFifo as u8 => FifoMode::StreamToFifo,
+            x if x == FifoMode::BypassToStream as u8 => FifoMode::BypassToStream,
+            x if x == FifoMode::DynamicStream as u8 => FifoMode::DynamicStream,
+            x if x == FifoMode::BypassToFifo as u8 => FifoMode::BypassToFifo,
+            // Bypass and the value reserved on both models
+            _ => FifoMode::Bypass,
+        }
+    }
+}
+
+/// FIFO watermark level (`FTH4-FTH0` of `FIFO_CTRL_REG`)
+#[derive(Clone, Copy)]
+struct FifoWatermark(u8);
+
+impl BitValue for FifoWatermark {
+    fn width() -> u8 {
+        5
+    }
+    fn shift() -> u8 {
+        0
+    }
+    fn value(&self) -> u8 {
+        self.0
+    }
+}
+
+/// Sensor configuration applied by `new_with_config`
+///
+/// Start from `Config::default()` and change the fields as needed, e.g.
+/// `Config { scale: Scale::Dps500, ..Config::default() }`.
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Status {
-    /// Overrun (data has overwritten previously unread data)
-    /// has occurred on at least one axis
-    pub overrun: bool,
-    /// Overrun occurred on Z-axis
-    pub z_overrun: bool,
-    /// Overrun occurred on Y-axis
-    pub y_overrun: bool,
-    /// Overrun occurred on X-axis
-    pub x_overrun: bool,
-    /// New data is available for either X, Y, Z - axis
-    pub new_data: bool,
-    /// New data is available on Z-axis
-    pub z_new: bool,
-    /// New data is available on Y-axis
-    pub y_new: bool,
-    /// New data is available on X-axis
-    pub x_new: bool,
+pub struct Config {
+    /// Output data rate
+    pub odr: Odr,
+    /// Low-pass cut-off
+    pub bandwidth: Bandwidth,
+    /// Full scale
+    pub scale: Scale,
+    /// Enabled axes, a combination of `AXES_X`, `AXES_Y` and `AXES_Z`
+    pub axes: u8,
+    /// Block data update
+    pub block_data_update: bool,
+    /// High-pass filter enabled
+    pub high_pass: bool,
+    /// High-pass filter mode
+    pub high_pass_mode: HighPassMode,
+    /// High-pass filter cutoff configuration
+    pub high_pass_cutoff: HighPassCutoff,
+    /// Output path selection
+    pub out_select: OutSelect,
 }
 
-impl Status {
-    fn from_u8(from: u8) -> Self {
-        Status {
-            overrun: (from & (1 << 7)) != 0,
-            z_overrun: (from & (1 << 6)) != 0,
-            y_overrun: (from & (1 << 5)) != 0,
-            x_overrun: (from & (1 << 4)) != 0,
-            new_data: (from & (1 << 3)) != 0,
-            z_new: (from & (1 << 2)) != 0,
-            y_new: (from & (1 << 1)) != 0,
-            x_new: (from & 1) != 0,
+impl Default for Config {
+    /// The configuration set by `new`: 95 Hz, lowest cut-off, 250 dps, all
+    /// axes enabled, block data update and the high-pass filter disabled
+    fn default() -> Self {
+        Config {
+            odr: Odr::Hz95,
+            bandwidth: Bandwidth::Low,
+            scale: Scale::Dps250,
+            axes: AXES_X | AXES_Y | AXES_Z,
+            block_data_update: false,
+            high_pass: false,
+            high_pass_mode: HighPassMode::NormalReset,
+            high_pass_cutoff: HighPassCutoff(0),
+            out_select: OutSelect::Unfiltered,
         }
     }
 }
+
+impl Config {
+    /// The configuration the sensor effectively uses
+    ///
+    /// Unknown axis bits are dropped. At 95 Hz, `Bandwidth::High` and
+    /// `Bandwidth::Maximum` select the same 25 Hz cut-off as
+    /// `Bandwidth::Medium` and are reported as such.
+    fn clamped(self) -> Self {
+        let bandwidth = match (self.odr, self.bandwidth) {
+            (Odr::Hz95, Bandwidth::High | Bandwidth::Maximum) => Bandwidth::Medium,
+            (_, bandwidth) => bandwidth,
+        };
+        Config {
+            bandwidth,
+            axes: self.axes & (AXES_X | AXES_Y | AXES_Z),
+            ..self
+        }
+    }
+
+    /// Configuration for a noise density of at most `target_dps_per_sqrt_hz`
+    ///
+    /// The noise density includes the quantization noise of the scale. Among
+    /// the configurations meeting the target, prefers the largest range, then
+    /// the highest output data rate and bandwidth. If none meets the target,
+    /// returns the one with the lowest noise density. All other settings are taken from `base`.
+    fn for_noise_density(target_dps_per_sqrt_hz: f32, base: Config) -> Self {
+        let target = target_dps_per_sqrt_hz * target_dps_per_sqrt_hz;
+        let mut closest = (base, f32::INFINITY);
+        for scale in [Scale::Dps2000, Scale::Dps500, Scale::Dps250] {
+            for odr in [Odr::Hz760, Odr::Hz380, Odr::Hz190, Odr::Hz95] {
+                for bandwidth in [
+                    Bandwidth::Maximum,
+                    Bandwidth::High,
+                    Bandwidth::Medium,
+                    Bandwidth::Low,
+                ] {
+                    let config = Config {
+                        odr,
+                        bandwidth,
+                        scale,
+                        ..base
+                    };
+                    // Squared noise density
+                    let density =
+                        Measurement::variance(odr, bandwidth, scale) / bandwidth.cutoff_hz(odr);
+                    if density <= target {
+                        return config.clamped();
+                    }
+                    if density < closest.1 {
+                        closest = (config, density);
+                    }
+                }
+            }
+        }
+        closest.0.clamped()
+    }
+
+    /// Decode `CTRL_REG1` to `CTRL_REG5`
+    fn from_registers(regs: &[u8; 5]) -> Self {
+        Config {
+            odr: Odr::from_u8(regs[0]),
+            bandwidth: Bandwidth::from_u8(regs[0]),
+            scale: Scale::from_u8(regs[3]),
+            axes: Self::axes_from_enable_bits(regs[0]),
+            block_data_update: regs[3] & CTRL_REG4_BDU != 0,
+            high_pass: regs[4] & CTRL_REG5_HPEN != 0,
+            high_pass_mode: HighPassMode::from_u8(regs[1]),
+            high_pass_cutoff: HighPassCutoff::from_u8(regs[1]),
+            out_select: OutSelect::from_u8(regs[4]),
+        }
+    }
+
+    /// Values of `CTRL_REG1` to `CTRL_REG5` with the sensor powered up
+    ///
+    /// Settings not covered by the configuration are at their power-up
+    /// defaults, i.e. no interrupts are routed, the byte order is little
+    /// endian and the FIFO is disabled.
+    fn registers(&self) -> [u8; 5] {
+        let reg2 = (self.high_pass_mode.value() << HighPassMode::shift())
+            | (self.high_pass_cutoff.value() << HighPassCutoff::shift());
+        let bit = |set: bool, bit: u8| if set { bit } else { 0 };
+        let reg4 =
+            (self.scale.value() << Scale::shift()) | bit(self.block_data_update, CTRL_REG4_BDU);
+        let reg5 =
+            (self.out_select.value() << OutSelect::shift()) | bit(self.high_pass, CTRL_REG5_HPEN);
+        [self.ctrl_reg1(), reg2, 0, reg4, reg5]
+    }
+
+    /// Value of `CTRL_REG1` with the sensor powered up
+    fn ctrl_reg1(&self) -> u8 {
+        (self.odr.value() << Odr::shift())
+            | (self.bandwidth.value() << Bandwidth::shift())
+            | CTRL_REG1_PD
+            | self.axes_enable_bits()
+    }
+
+    /// Selection mask of the axes enabled in `reg1`
+    fn axes_from_enable_bits(reg1: u8) -> u8 {
+        Axis::ALL
+            .into_iter()
+            .filter(|axis| reg1 & axis.enable_bit() != 0)
+            .fold(0, |axes, axis| axes | axis.mask_bit())
+    }
+
+    /// Enable bits in `CTRL_REG1` of the axes selected in `axes`
+    fn axes_enable_bits(&self) -> u8 {
+        Axis::ALL
+            .into_iter()
+            .filter(|axis| self.axes & axis.mask_bit() != 0)
+            .fold(0, |bits, axis| bits | axis.enable_bit())
+    }
+}
+
+impl Register {
+    fn addr(self) -> u8 {
+        self as u8
+    }
+
+    /// Whether writing the register affects the measurement output path
+    fn affects_output(self) -> bool {
+        matches!(
+            self,
+            Register::CTRL_REG1
+                | Register::CTRL_REG2
+                | Register::CTRL_REG4
+                | Register::CTRL_REG5
+                | Register::REFERENCE
+        )
+    }
+}
+
+/// Gyroscope output registers in burst read order
+const GYRO_OUT_REGISTERS: [Register; 6] = [
+    Register::OUT_X_L,
+    Register::OUT_X_H,
+    Register::OUT_Y_L,
+    Register::OUT_Y_H,
+    Register::OUT_Z_L,
+    Register::OUT_Z_H,
+];
+
+/// Number of measurements averaged at the start and at the end of `verify_hp_filter`
+pub const HP_FILTER_VERIFICATION_SAMPLES: usize = 16;
+
+/// Residual rate in degrees per second below which `verify_hp_filter`
+/// considers the output converged
+pub const HP_FILTER_RESIDUAL_DPS: f32 = 0.5;
+
+/// Number of measurements averaged by `calibrate_thermal_reference`
+pub const THERMAL_CALIBRATION_SAMPLES: u16 = 32;
+
+/// Time in microseconds `reboot` waits for the sensor to reload its trimming
+/// values
+pub const BOOT_TIME_US: u32 = 10_000;
+
+/// Identity matrix, the default mount matrix
+const IDENTITY: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+/// Number of measurements used for the noise floor check of `post`
+pub const POST_NOISE_SAMPLES: usize = 32;
+
+/// Default temperature reference in degrees celcius, the raw temperature
+/// measurement is subtracted from it
+pub const TEMP_OFFSET_DEFAULT: i16 = 25;
+
+/// Rate noise density in degrees per second per square root of Hz, typical
+/// value from the data sheet
+pub const RATE_NOISE_DENSITY: f32 = 0.03;
+
+/// Number of output data periods `gyro_settled` waits after a configuration
+/// change for the filters to settle
+pub const SETTLING_PERIODS: u32 = 5;
+
+/// Filter coefficient of `update_bias_if_idle` as a power of two, i.e. the bias
+/// moves by 1/64 of the difference per update
+const BIAS_TRACKING_SHIFT: u32 = 6;
+
+/// Block data update bit (`BDU`) of `CTRL_REG4`
+const CTRL_REG4_BDU: u8 = 1 << 7;
+
+/// Depth of the FIFO in samples
+pub const FIFO_DEPTH: usize = 32;
+
+/// Reboot memory content bit (`BOOT`) of `CTRL_REG5`
+const CTRL_REG5_BOOT: u8 = 1 << 7;
+
+/// High-pass filter enable bit (`HPen`) of `CTRL_REG5`
+const CTRL_REG5_HPEN: u8 = 1 << 4;
+/// Output selection bits (`Out_Sel`) of `CTRL_REG5`, `0b00` bypasses the high-pass filter
+const CTRL_REG5_OUT_SEL: u8 = 0b0000_0011;
+/// Big/little endian data selection bit (`BLE`) of `CTRL_REG4`
+const CTRL_REG4_BLE: u8 = 1 << 6;
+/// SPI serial interface mode bit (`SIM`) of `CTRL_REG4`
+const CTRL_REG4_SIM: u8 = 1;
+/// FIFO enable bit (`FIFO_EN`) of `CTRL_REG5`
+const CTRL_REG5_FIFO_EN: u8 = 1 << 6;
+/// Watermark threshold bits (`WTM4-WTM0`) of `FIFO_CTRL_REG`
+const FIFO_CTRL_WTM: u8 = 0b0001_1111;
+/// Watermark status bit (`WTM`) of `FIFO_SRC_REG`
+const FIFO_SRC_WTM: u8 = 1 << 7;
+/// Overrun bit (`OVRN`) of `FIFO_SRC_REG`
+const FIFO_SRC_OVRN: u8 = 1 << 6;
+/// FIFO empty bit (`EMPTY`) of `FIFO_SRC_REG`
+const FIFO_SRC_EMPTY: u8 = 1 << 5;
+/// Stored data level bits (`FSS4-FSS0`) of `FIFO_SRC_REG`
+const FIFO_SRC_FSS: u8 = 0b0001_1111;
+
+/// Writable configuration registers, in restore order
+///
+/// `CTRL_REG1` comes last so the power mode is restored after everything
+/// else.
+const STATE_REGISTERS: [Register; 14] = [
+    Register::CTRL_REG2,
+    Register::CTRL_REG3,
+    Register::CTRL_REG4,
+    Register::CTRL_REG5,
+    Register::REFERENCE,
+    Register::FIFO_CTRL_REG,
+    Register::INT1_CFG,
+    Register::INT1_TSH_XH,
+    Register::INT1_TSH_XL,
+    Register::INT1_TSH_YH,
+    Register::INT1_TSH_YL,
+    Register::INT1_TSH_ZH,
+    Register::INT1_TSH_ZL,
+    Register::INT1_DURATION,
+];
+
+/// Interrupt 1 configuration registers, excluding the read-only `INT1_SRC`
+const INT1_CONFIG_REGISTERS: [Register; 8] = [
+    Register::INT1_CFG,
+    Register::INT1_TSH_XH,
+    Register::INT1_TSH_XL,
+    Register::INT1_TSH_YH,
+    Register::INT1_TSH_YL,
+    Register::INT1_TSH_ZH,
+    Register::INT1_TSH_ZL,
+    Register::INT1_DURATION,
+];
+
+/// Interrupt enable on INT1 pin bit (`I1_Int1`) of `CTRL_REG3`
+const CTRL_REG3_I1_INT1: u8 = 1 << 7;
+/// FIFO watermark interrupt on DRDY/INT2 pin bit (`I2_WTM`) of `CTRL_REG3`
+const CTRL_REG3_I2_WTM: u8 = 1 << 2;
+/// Interrupt active low bit (`H_Lactive`) of `CTRL_REG3`
+const CTRL_REG3_H_LACTIVE: u8 = 1 << 5;
+/// Boot status on INT1 pin bit (`I1_Boot`) of `CTRL_REG3`
+const CTRL_REG3_I1_BOOT: u8 = 1 << 6;
+/// Open drain bit (`PP_OD`) of `CTRL_REG3`
+const CTRL_REG3_PP_OD: u8 = 1 << 4;
+/// Data ready on DRDY/INT2 pin bit (`I2_DRDY`) of `CTRL_REG3`
+const CTRL_REG3_I2_DRDY: u8 = 1 << 3;
+/// FIFO overrun interrupt on DRDY/INT2 pin bit (`I2_ORun`) of `CTRL_REG3`
+const CTRL_REG3_I2_ORUN: u8 = 1 << 1;
+/// FIFO empty interrupt on DRDY/INT2 pin bit (`I2_Empty`) of `CTRL_REG3`
+const CTRL_REG3_I2_EMPTY: u8 = 1;
+/// Low output data rate bit (`Low_ODR`) of the L3GD20H `LOW_ODR` register
+const LOW_ODR_EN: u8 = 1;
+/// Test patterns written to `INT1_TSH_XL` by the loopback check of `post`
+const LOOPBACK_PATTERNS: [u8; 2] = [0x55, 0xAA];
+/// All high and low event enable bits of `INT1_CFG`
+const INT1_CFG_ALL_EVENTS: u8 = 0b0011_1111;
+/// Largest interrupt 1 threshold, the thresholds are 15 bit wide
+const INT1_THRESHOLD_MAX: u16 = 0x7FFF;
+/// Interrupt active bit (`IA`) of `INT1_SRC`
+const INT1_SRC_IA: u8 = 1 << 6;
+/// Wait bit (`WAIT`) of `INT1_DURATION`
+const INT1_DURATION_WAIT: u8 = 1 << 7;
+/// Duration counter in `INT1_DURATION`
+const INT1_DURATION_D: u8 = 0b0111_1111;
+
+/// Power-down control bit (`PD`) of `CTRL_REG1`
+const CTRL_REG1_PD: u8 = 1 << 3;
+/// Axis enable bits (`Zen`, `Xen`, `Yen`) of `CTRL_REG1`
+const CTRL_REG1_AXES: u8 = 0b0000_0111;
+
+impl Scale {
+    /// Sensitivity in degrees per second per LSB
+    pub fn dps_per_lsb(&self) -> f32 {
+        match *self {
+            Scale::Dps250 => 0.00875,
+            Scale::Dps500 => 0.0175,
+            Scale::Dps2000 => 0.07,
+        }
+    }
+
+    /// Typical output change in degrees per second in self-test mode
+    /// according to the data sheet
+    pub fn self_test_dps(&self) -> f32 {
+        match *self {
+            Scale::Dps250 => 130.0,
+            Scale::Dps500 => 200.0,
+            Scale::Dps2000 => 530.0,
+        }
+    }
+
+    /// Sensitivity in micro degrees per second per LSB
+    fn micro_dps_per_lsb(&self) -> i64 {
+        match *self {
+            Scale::Dps250 => 8_750,
+            Scale::Dps500 => 17_500,
+            Scale::Dps2000 => 70_000,
+        }
+    }
+
+    /// Convert a measurement to the resolution of `lsb_per_dps` LSB per
+    /// degree per second, rounded to the nearest integer
+    fn rescale(&self, val: i16, lsb_per_dps: u16) -> i32 {
+        let scaled = val as i64 * self.micro_dps_per_lsb() * lsb_per_dps as i64;
+        let half = if scaled < 0 { -500_000 } else { 500_000 };
+        ((scaled + half) / 1_000_000) as i32
+    }
+
+    /// Convert a measurement to degrees
+    pub fn degrees(&self, val: i16) -> f32 {
+        val as f32 * self.dps_per_lsb()
+    }
+
+    /// Convert degrees per second to the nearest raw measurement value
+    ///
+    /// Rates beyond the range of `i16` saturate at `i16::MIN`/`i16::MAX`.
+    pub fn to_raw(&self, dps: f32) -> i16 {
+        let raw = dps / self.dps_per_lsb();
+        // Round half away from zero, the cast saturates
+        if raw >= 0.0 {
+            (raw + 0.5) as i16
+        } else {
+            (raw - 0.5) as i16
+        }
+    }
+
+    /// Convert a gyroscope measurement to degrees per second
+    fn degrees_f32x3(&self, val: I16x3) -> F32x3 {
+        F32x3 {
+            x: self.degrees(val.x),
+            y: self.degrees(val.y),
+            z: self.degrees(val.z),
+        }
+    }
+
+    /// Convert a measurement to radians
+    ///
+    /// Uses `f32::to_radians` from `core` with and without the `libm`
+    /// feature, so enabling features never changes the result.
+    pub fn radians(&self, val: i16) -> f32 {
+        self.degrees(val).to_radians()
+    }
+
+    /// Convert a gyroscope measurement to degrees per second, in X, Y, Z
+    /// order
+    pub fn degrees_triple(&self, v: I16x3) -> [f32; 3] {
+        [self.degrees(v.x), self.degrees(v.y), self.degrees(v.z)]
+    }
+
+    /// Convert a gyroscope measurement to radians per second, in X, Y, Z
+    /// order
+    pub fn radians_triple(&self, v: I16x3) -> [f32; 3] {
+        [self.radians(v.x), self.radians(v.y), self.radians(v.z)]
+    }
+}
+
+/// Gyroscope bias in fixed point with `Bias::FRAC_BITS` fractional bits
+///
+/// The fractional bits allow slow filtering of the bias without the update
+/// being rounded away.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Bias([i32; 3]);
+
+impl Bias {
+    const FRAC_BITS: u32 = 8;
+
+    fn from_i16x3(bias: I16x3) -> Self {
+        Bias([
+            (bias.x as i32) << Self::FRAC_BITS,
+            (bias.y as i32) << Self::FRAC_BITS,
+            (bias.z as i32) << Self::FRAC_BITS,
+        ])
+    }
+
+    /// Mean of `n` measurements with per-axis sums `sums`, keeping the
+    /// fractional part
+    fn from_sums(sums: [i64; 3], n: u16) -> Self {
+        Bias(sums.map(|sum| ((sum << Self::FRAC_BITS) / n as i64) as i32))
+    }
+
+    /// Round a fixed point value to the nearest LSB, saturating
+    fn round(val: i32) -> i16 {
+        ((val + (1 << (Self::FRAC_BITS - 1))) >> Self::FRAC_BITS)
+            .clamp(i16::MIN as i32, i16::MAX as i32) as i16
+    }
+
+    fn to_i16x3(self) -> I16x3 {
+        I16x3 {
+            x: Self::round(self.0[0]),
+            y: Self::round(self.0[1]),
+            z: Self::round(self.0[2]),
+        }
+    }
+
+    /// Subtract the bias from a measurement
+    fn apply(&self, raw: I16x3) -> I16x3 {
+        let sub = |raw: i16, bias: i32| Self::round(((raw as i32) << Self::FRAC_BITS) - bias);
+        I16x3 {
+            x: sub(raw.x, self.0[0]),
+            y: sub(raw.y, self.0[1]),
+            z: sub(raw.z, self.0[2]),
+        }
+    }
+
+    /// Move the bias towards `raw` by `1 / 2^shift` of the difference
+    fn track(&mut self, raw: I16x3, shift: u32) {
+        for (bias, raw) in self.0.iter_mut().zip([raw.x, raw.y, raw.z]) {
+            *bias += (((raw as i32) << Self::FRAC_BITS) - *bias) >> shift;
+        }
+    }
+}
+
+/// Sensor axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Axis {
+    /// X-axis
+    X,
+    /// Y-axis
+    Y,
+    /// Z-axis
+    Z,
+}
+
+impl Axis {
+    const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    /// Bit of the axis in the `AXES_X`, `AXES_Y` and `AXES_Z` selection mask
+    fn mask_bit(self) -> u8 {
+        match self {
+            Axis::X => AXES_X,
+            Axis::Y => AXES_Y,
+            Axis::Z => AXES_Z,
+        }
+    }
+
+    /// Enable bit of the axis in `CTRL_REG1`
+    fn enable_bit(self) -> u8 {
+        match self {
+            Axis::X => 1 << 1,
+            Axis::Y => 1,
+            Axis::Z => 1 << 2,
+        }
+    }
+
+    /// `INT1_TSH_xH` and `INT1_TSH_xL` registers of the axis
+    fn int1_threshold_registers(self) -> (Register, Register) {
+        match self {
+            Axis::X => (Register::INT1_TSH_XH, Register::INT1_TSH_XL),
+            Axis::Y => (Register::INT1_TSH_YH, Register::INT1_TSH_YL),
+            Axis::Z => (Register::INT1_TSH_ZH, Register::INT1_TSH_ZL),
+        }
+    }
+
+    /// Index of the axis in per-axis arrays, in X, Y, Z order
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
+
+/// Interrupt 1 generator configuration (`INT1_CFG`)
+///
+/// Built from `Int1Config::default()`, which disables all events, e.g.
+/// `Int1Config::default().high(Axis::Z).latched(true)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Int1Config {
+    /// High event enable per axis, in X, Y, Z order
+    pub high: [bool; 3],
+    /// Low event enable per axis, in X, Y, Z order
+    pub low: [bool; 3],
+    /// Raise the interrupt only if all enabled events occur, instead of any
+    pub and_events: bool,
+    /// Latch the interrupt until `INT1_SRC` is read
+    pub latch: bool,
+}
+
+impl Int1Config {
+    /// Enable the interrupt on a rate above the high threshold of `axis`
+    pub fn high(mut self, axis: Axis) -> Self {
+        self.high[axis.index()] = true;
+        self
+    }
+
+    /// Enable the interrupt on a rate below the low threshold of `axis`
+    pub fn low(mut self, axis: Axis) -> Self {
+        self.low[axis.index()] = true;
+        self
+    }
+
+    /// Combine the enabled events with AND instead of OR
+    pub fn and_events(mut self, and: bool) -> Self {
+        self.and_events = and;
+        self
+    }
+
+    /// Latch the interrupt until `INT1_SRC` is read
+    pub fn latched(mut self, latch: bool) -> Self {
+        self.latch = latch;
+        self
+    }
+
+    fn from_u8(from: u8) -> Self {
+        let bit = |n: u8| from & (1 << n) != 0;
+        Int1Config {
+            high: [bit(1), bit(3), bit(5)],
+            low: [bit(0), bit(2), bit(4)],
+            and_events: bit(7),
+            latch: bit(6),
+        }
+    }
+
+    /// Value of `INT1_CFG`
+    fn value(&self) -> u8 {
+        let mut cfg = 0;
+        for i in 0..3 {
+            cfg |= (self.low[i] as u8) << (2 * i) | (self.high[i] as u8) << (2 * i + 1);
+        }
+        cfg | (self.latch as u8) << 6 | (self.and_events as u8) << 7
+    }
+}
+
+/// Interrupt pin configuration (`CTRL_REG3`)
+///
+/// The default is the power-up state: nothing routed to the pins, active
+/// high and push-pull.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ctrl3Config {
+    /// Interrupt 1 on the INT1 pin (`I1_Int1`)
+    pub int1: bool,
+    /// Boot status on the INT1 pin (`I1_Boot`)
+    pub boot: bool,
+    /// Interrupt pins are active low instead of active high (`H_Lactive`)
+    pub active_low: bool,
+    /// Interrupt pins are open drain instead of push-pull (`PP_OD`)
+    pub open_drain: bool,
+    /// Data ready on the DRDY/INT2 pin (`I2_DRDY`)
+    pub drdy: bool,
+    /// FIFO watermark interrupt on the DRDY/INT2 pin (`I2_WTM`)
+    pub watermark: bool,
+    /// FIFO overrun interrupt on the DRDY/INT2 pin (`I2_ORun`)
+    pub overrun: bool,
+    /// FIFO empty interrupt on the DRDY/INT2 pin (`I2_Empty`)
+    pub empty: bool,
+}
+
+impl Ctrl3Config {
+    fn from_u8(from: u8) -> Self {
+        Ctrl3Config {
+            int1: from & CTRL_REG3_I1_INT1 != 0,
+            boot: from & CTRL_REG3_I1_BOOT != 0,
+            active_low: from & CTRL_REG3_H_LACTIVE != 0,
+            open_drain: from & CTRL_REG3_PP_OD != 0,
+            drdy: from & CTRL_REG3_I2_DRDY != 0,
+            watermark: from & CTRL_REG3_I2_WTM != 0,
+            overrun: from & CTRL_REG3_I2_ORUN != 0,
+            empty: from & CTRL_REG3_I2_EMPTY != 0,
+        }
+    }
+
+    /// Value of `CTRL_REG3`
+    fn value(&self) -> u8 {
+        let bit = |set: bool, bit: u8| if set { bit } else { 0 };
+        bit(self.int1, CTRL_REG3_I1_INT1)
+            | bit(self.boot, CTRL_REG3_I1_BOOT)
+            | bit(self.active_low, CTRL_REG3_H_LACTIVE)
+            | bit(self.open_drain, CTRL_REG3_PP_OD)
+            | bit(self.drdy, CTRL_REG3_I2_DRDY)
+            | bit(self.watermark, CTRL_REG3_I2_WTM)
+            | bit(self.overrun, CTRL_REG3_I2_ORUN)
+            | bit(self.empty, CTRL_REG3_I2_EMPTY)
+    }
+}
+
+/// Interrupt 1 source (`INT1_SRC`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Int1Source {
+    /// One or more interrupts are active
+    pub active: bool,
+    /// X-axis high event
+    pub x_high: bool,
+    /// X-axis low event
+    pub x_low: bool,
+    /// Y-axis high event
+    pub y_high: bool,
+    /// Y-axis low event
+    pub y_low: bool,
+    /// Z-axis high event
+    pub z_high: bool,
+    /// Z-axis low event
+    pub z_low: bool,
+}
+
+impl Int1Source {
+    fn from_u8(from: u8) -> Self {
+        let bit = |n: u8| from & (1 << n) != 0;
+        Int1Source {
+            active: from & INT1_SRC_IA != 0,
+            x_high: bit(1),
+            x_low: bit(0),
+            y_high: bit(3),
+            y_low: bit(2),
+            z_high: bit(5),
+            z_low: bit(4),
+        }
+    }
+}
+
+/// Direction of an interrupt threshold crossing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// Rate above the high threshold
+    High,
+    /// Rate below the low threshold
+    Low,
+}
+
+impl Direction {
+    /// First event flagged in `INT1_SRC`, in X, Y, Z order with high events
+    /// first, or `None` if no interrupt is active
+    fn from_int1_src(src: u8) -> Option<(Axis, Direction)> {
+        if src & INT1_SRC_IA == 0 {
+            return None;
+        }
+        [(Axis::X, 0), (Axis::Y, 2), (Axis::Z, 4)]
+            .into_iter()
+            .flat_map(|(axis, shift)| {
+                [
+                    (axis, Direction::High, 2 << shift),
+                    (axis, Direction::Low, 1 << shift),
+                ]
+            })
+            .find(|&(_, _, bit)| src & bit != 0)
+            .map(|(axis, direction, _)| (axis, direction))
+    }
+}
+
+/// XYZ triple
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct I16x3 {
+    /// X component
+    pub x: i16,
+    /// Y component
+    pub y: i16,
+    /// Z component
+    pub z: i16,
+}
+
+impl I16x3 {
+    /// Incremental rotation matrix for rotating at this rate for `dt_s` seconds
+    ///
+    /// The rate is converted with `scale` and the rotation by the angle
+    /// vector `rate * dt_s` is computed with the Rodrigues formula, which
+    /// reduces to the small-angle approximation `I + [angle]x` for short time
+    /// steps. Multiply a direction cosine matrix by the result to propagate it.
+    #[cfg(feature = "libm")]
+    pub fn to_rotation_update(self, scale: Scale, dt_s: f32) -> [[f32; 3]; 3] {
+        let x = scale.radians(self.x) * dt_s;
+        let y = scale.radians(self.y) * dt_s;
+        let z = scale.radians(self.z) * dt_s;
+        let angle_sq = x * x + y * y + z * z;
+        let angle = libm::sqrtf(angle_sq);
+        let (a, b) = if angle > f32::EPSILON {
+            (
+                libm::sinf(angle) / angle,
+                (1.0 - libm::cosf(angle)) / angle_sq,
+            )
+        } else {
+            (1.0, 0.5)
+        };
+
+        // I + a * K + b * K², K being the skew-symmetric matrix of the angle
+        [
+            [
+                1.0 - b * (y * y + z * z),
+                -a * z + b * x * y,
+                a * y + b * x * z,
+            ],
+            [
+                a * z + b * x * y,
+                1.0 - b * (x * x + z * z),
+                -a * x + b * y * z,
+            ],
+            [
+                -a * y + b * x * z,
+                a * x + b * y * z,
+                1.0 - b * (x * x + y * y),
+            ],
+        ]
+    }
+
+    /// Decode the output registers in `OUT_X_L` to `OUT_Z_H` order
+    fn from_bytes(bytes: &[u8]) -> Self {
+        I16x3 {
+            x: (bytes[0] as u16 + ((bytes[1] as u16) << 8)) as i16,
+            y: (bytes[2] as u16 + ((bytes[3] as u16) << 8)) as i16,
+            z: (bytes[4] as u16 + ((bytes[5] as u16) << 8)) as i16,
+        }
+    }
+
+    /// Decode the output registers with the high byte first, as with `BLE` set
+    fn from_bytes_be(bytes: &[u8]) -> Self {
+        I16x3 {
+            x: i16::from_be_bytes([bytes[0], bytes[1]]),
+            y: i16::from_be_bytes([bytes[2], bytes[3]]),
+            z: i16::from_be_bytes([bytes[4], bytes[5]]),
+        }
+    }
+
+    /// Decode the output registers in the byte order selected by `BLE`
+    fn from_bytes_ordered(bytes: &[u8], big_endian: bool) -> Self {
+        if big_endian {
+            I16x3::from_bytes_be(bytes)
+        } else {
+            I16x3::from_bytes(bytes)
+        }
+    }
+
+    /// Negate the components selected by `invert` (X, Y, Z), saturating
+    fn inverted(self, invert: [bool; 3]) -> Self {
+        let apply = |val: i16, invert: bool| if invert { val.saturating_neg() } else { val };
+        I16x3 {
+            x: apply(self.x, invert[0]),
+            y: apply(self.y, invert[1]),
+            z: apply(self.z, invert[2]),
+        }
+    }
+
+    /// Whether each component hits the full-scale limit of the output
+    ///
+    /// The output clips at the limits of `i16`, which is what
+    /// `clipping_stats` and `measure_recovery_time` check for.
+    fn saturated(&self) -> [bool; 3] {
+        [self.x, self.y, self.z].map(|val| val == i16::MAX || val == i16::MIN)
+    }
+
+    /// Largest magnitude of the three components
+    fn abs_max(&self) -> u16 {
+        self.x
+            .unsigned_abs()
+            .max(self.y.unsigned_abs())
+            .max(self.z.unsigned_abs())
+    }
+
+    /// Component of the given axis
+    pub fn axis(&self, axis: Axis) -> i16 {
+        match axis {
+            Axis::X => self.x,
+            Axis::Y => self.y,
+            Axis::Z => self.z,
+        }
+    }
+}
+
+/// XYZ triple of floating point values
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct F32x3 {
+    /// X component
+    pub x: f32,
+    /// Y component
+    pub y: f32,
+    /// Z component
+    pub z: f32,
+}
+
+impl F32x3 {
+    /// Components in X, Y, Z order
+    fn to_array(self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+
+    /// Multiply by the 3x3 matrix `m`, i.e. `m * self`
+    fn transformed(self, m: &[[f32; 3]; 3]) -> Self {
+        let row = |r: &[f32; 3]| r[0] * self.x + r[1] * self.y + r[2] * self.z;
+        F32x3 {
+            x: row(&m[0]),
+            y: row(&m[1]),
+            z: row(&m[2]),
+        }
+    }
+}
+
+impl Integrator for F32x3 {
+    /// Rectangular integration of the rate into angles in degrees
+    fn accumulate(&mut self, rate: F32x3, dt: f32) {
+        self.x += rate.x * dt;
+        self.y += rate.y * dt;
+        self.z += rate.z * dt;
+    }
+}
+
+/// Fixed-size window of gyroscope measurements
+///
+/// Once `N` measurements have been pushed, every new measurement replaces the
+/// oldest one. Used to compute the per-axis variance of the most recent
+/// measurements without allocating.
+#[derive(Debug, Clone, Copy)]
+pub struct VarWindow<const N: usize> {
+    samples: [I16x3; N],
+    next: usize,
+    len: usize,
+}
+
+impl<const N: usize> VarWindow<N> {
+    /// Create an empty window
+    pub const fn new() -> Self {
+        VarWindow {
+            samples: [I16x3 { x: 0, y: 0, z: 0 }; N],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Add a measurement, replacing the oldest one if the window is full
+    pub fn push(&mut self, sample: I16x3) {
+        if N == 0 {
+            return;
+        }
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Number of measurements currently in the window
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the window contains no measurements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Per-axis mean of the measurements in the window, in raw LSB
+    pub fn mean(&self) -> F32x3 {
+        let mut mean = F32x3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        if self.is_empty() {
+            return mean;
+        }
+        for sample in &self.samples[..self.len] {
+            mean.x += sample.x as f32;
+            mean.y += sample.y as f32;
+            mean.z += sample.z as f32;
+        }
+        let n = self.len as f32;
+        mean.x /= n;
+        mean.y /= n;
+        mean.z /= n;
+        mean
+    }
+
+    /// Per-axis population variance of the measurements in the window, in raw LSB²
+    pub fn variance(&self) -> F32x3 {
+        let mean = self.mean();
+        let mut var = F32x3 {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        if self.is_empty() {
+            return var;
+        }
+        for sample in &self.samples[..self.len] {
+            let (dx, dy, dz) = (
+                sample.x as f32 - mean.x,
+                sample.y as f32 - mean.y,
+                sample.z as f32 - mean.z,
+            );
+            var.x += dx * dx;
+            var.y += dy * dy;
+            var.z += dz * dz;
+        }
+        let n = self.len as f32;
+        var.x /= n;
+        var.y /= n;
+        var.z /= n;
+        var
+    }
+}
+
+impl<const N: usize> Default for VarWindow<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accumulator for the Allan variance of a stream of measurements
+///
+/// Measurements are averaged into non-overlapping clusters of 1, 2, 4, ...,
+/// 2^(N-1) samples. The squared differences between consecutive cluster means
+/// are accumulated incrementally, so no measurements are stored. Cluster
+/// lengths are given in samples; multiply by `Odr::period_us` for time.
+#[derive(Debug, Clone, Copy)]
+pub struct AllanAccumulator<const N: usize> {
+    sums: [[i64; 3]; N],
+    fill: [u32; N],
+    prev: [[f32; 3]; N],
+    clusters: [u32; N],
+    squares: [[f32; 3]; N],
+}
+
+impl<const N: usize> AllanAccumulator<N> {
+    /// Create an empty accumulator
+    pub const fn new() -> Self {
+        AllanAccumulator {
+            sums: [[0; 3]; N],
+            fill: [0; N],
+            prev: [[0.0; 3]; N],
+            clusters: [0; N],
+            squares: [[0.0; 3]; N],
+        }
+    }
+
+    /// Add a measurement
+    pub fn push(&mut self, sample: I16x3) {
+        let sample = [sample.x, sample.y, sample.z];
+        for level in 0..N {
+            for (sum, value) in self.sums[level].iter_mut().zip(sample) {
+                *sum += value as i64;
+            }
+            self.fill[level] += 1;
+            if self.fill[level] < 1 << level {
+                continue;
+            }
+
+            let len = self.fill[level] as f32;
+            let mut mean = [0.0; 3];
+            for (mean, sum) in mean.iter_mut().zip(self.sums[level]) {
+                *mean = sum as f32 / len;
+            }
+            if self.clusters[level] > 0 {
+                let squares = self.squares[level].iter_mut();
+                for ((square, mean), prev) in squares.zip(mean).zip(self.prev[level]) {
+                    *square += (mean - prev) * (mean - prev);
+                }
+            }
+            self.prev[level] = mean;
+            self.clusters[level] = self.clusters[level].saturating_add(1);
+            self.sums[level] = [0; 3];
+            self.fill[level] = 0;
+        }
+    }
+
+    /// Number of complete clusters of `tau` samples
+    ///
+    /// Returns 0 if `tau` is not a power of two below 2^N.
+    pub fn clusters(&self, tau: usize) -> u32 {
+        Self::level(tau).map_or(0, |level| self.clusters[level])
+    }
+
+    /// Per-axis Allan variance for a cluster length of `tau` samples, in raw LSB²
+    ///
+    /// Returns `None` if `tau` is not a power of two below 2^N or fewer than
+    /// two clusters of that length have been accumulated.
+    pub fn variance(&self, tau: usize) -> Option<F32x3> {
+        let level = Self::level(tau)?;
+        if self.clusters[level] < 2 {
+            return None;
+        }
+        let n = 2.0 * (self.clusters[level] - 1) as f32;
+        let [x, y, z] = self.squares[level];
+        Some(F32x3 {
+            x: x / n,
+            y: y / n,
+            z: z / n,
+        })
+    }
+
+    /// Per-axis Allan deviation for a cluster length of `tau` samples, in raw LSB
+    ///
+    /// See `variance`.
+    #[cfg(feature = "libm")]
+    pub fn deviation(&self, tau: usize) -> Option<F32x3> {
+        let var = self.variance(tau)?;
+        Some(F32x3 {
+            x: libm::sqrtf(var.x),
+            y: libm::sqrtf(var.y),
+            z: libm::sqrtf(var.z),
+        })
+    }
+
+    fn level(tau: usize) -> Option<usize> {
+        if !tau.is_power_of_two() {
+            return None;
+        }
+        let level = tau.trailing_zeros() as usize;
+        (level < N).then_some(level)
+    }
+}
+
+impl<const N: usize> Default for AllanAccumulator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// First-order model of the bias drift caused by self-heating
+///
+/// The bias is in degrees per second and applies to bias and sign corrected
+/// measurements, i.e. in addition to the bias set with `set_bias`.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ThermalModel {
+    /// Temperature in degrees celcius at which `bias_dps` applies
+    pub reference_celcius: f32,
+    /// Bias at the reference temperature
+    pub bias_dps: F32x3,
+    /// Change of the bias per degree celcius
+    pub slope_dps_per_celcius: F32x3,
+}
+
+impl ThermalModel {
+    /// Modelled bias in degrees per second at `celcius`
+    pub fn bias_at(&self, celcius: i16) -> F32x3 {
+        let delta = celcius as f32 - self.reference_celcius;
+        F32x3 {
+            x: self.bias_dps.x + self.slope_dps_per_celcius.x * delta,
+            y: self.bias_dps.y + self.slope_dps_per_celcius.y * delta,
+            z: self.bias_dps.z + self.slope_dps_per_celcius.z * delta,
+        }
+    }
+}
+
+/// Several measurements
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Measurements {
+    /// Gyroscope measurements
+    pub gyro: I16x3,
+    /// Raw temperature sensor measurement
+    pub temp_raw: i8,
+}
+
+impl Measurements {
+    /// Convert the raw temperature value to degrees celcius
+    ///
+    /// Uses the default reference `TEMP_OFFSET_DEFAULT`, see
+    /// `temp_celcius_with_offset`.
+    pub fn temp_celcius(&self) -> i16 {
+        self.temp_celcius_with_offset(TEMP_OFFSET_DEFAULT)
+    }
+
+    /// Convert the raw temperature value to degrees celcius as
+    /// `offset - temp_raw`
+    pub fn temp_celcius_with_offset(&self, offset: i16) -> i16 {
+        offset - self.temp_raw as i16
+    }
+}
+
+/// Several measurements in physical units
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ScaledMeasurements {
+    /// Gyroscope measurements in degrees per second, in X, Y, Z order
+    pub gyro: [f32; 3],
+    /// Temperature in degrees celcius
+    pub temp_c: i16,
+}
+
+/// Gyroscope input for one step of a complementary filter
+///
+/// A complementary filter blends `angle + angle_delta_deg` with the angle
+/// derived from an accelerometer, e.g.
+/// `angle = a * (angle + delta) + (1 - a) * accel_angle`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ComplementaryInput {
+    /// Angular rate in degrees per second
+    pub rate_dps: F32x3,
+    /// Per-axis angle change over `dt_s` in degrees
+    pub angle_delta_deg: F32x3,
+    /// Time step in seconds
+    pub dt_s: f32,
+}
+
+/// Result of the power-on self test run by `post`
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PostReport {
+    /// Model detected from `WHO_AM_I`, `None` if the value is unknown
+    pub model: Option<Model>,
+    /// Test patterns written to a register were read back unchanged
+    pub loopback: bool,
+    /// Output change in raw LSB caused by the built-in self-test
+    pub self_test: I16x3,
+    /// The self-test output change of every axis is plausible
+    pub self_test_passed: bool,
+    /// Per-axis variance at rest in (degrees per second)^2
+    pub noise_variance: F32x3,
+    /// The noise at rest is within the expected noise floor
+    pub noise_passed: bool,
+}
+
+impl PostReport {
+    /// Whether all checks passed
+    pub fn passed(&self) -> bool {
+        self.model.is_some() && self.loopback && self.self_test_passed && self.noise_passed
+    }
+}
+
+/// Angular rate together with its expected noise, e.g. as the measurement of
+/// a Kalman filter
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Measurement {
+    /// Angular rate in degrees per second
+    pub rate: F32x3,
+    /// Per-axis variance of `rate` in (degrees per second)^2
+    pub variance: F32x3,
+}
+
+impl Measurement {
+    /// Variance of a single measurement
+    ///
+    /// Rate noise integrated over the low-pass bandwidth plus the quantization
+    /// noise of one LSB at `scale`.
+    fn variance(odr: Odr, bandwidth: Bandwidth, scale: Scale) -> f32 {
+        let lsb = scale.dps_per_lsb();
+        RATE_NOISE_DENSITY * RATE_NOISE_DENSITY * bandwidth.cutoff_hz(odr) + lsb * lsb / 12.0
+    }
+}
+
+/// Gyroscope sample with C-compatible layout
+///
+/// Can be shared with C code or copied to a host as-is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GyroSampleC {
+    /// X component
+    pub x: i16,
+    /// Y component
+    pub y: i16,
+    /// Z component
+    pub z: i16,
+    /// Raw temperature sensor measurement
+    pub temp: i8,
+    /// Raw value of `STATUS_REG`
+    pub status: u8,
+}
+
+/// Sensor status
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status {
+    /// Overrun (data has overwritten previously unread data)
+    /// has occurred on at least one axis
+    pub overrun: bool,
+    /// Overrun occurred on Z-axis
+    pub z_overrun: bool,
+    /// Overrun occurred on Y-axis
+    pub y_overrun: bool,
+    /// Overrun occurred on X-axis
+    pub x_overrun: bool,
+    /// New data is available for either X, Y, Z - axis
+    pub new_data: bool,
+    /// New data is available on Z-axis
+    pub z_new: bool,
+    /// New data is available on Y-axis
+    pub y_new: bool,
+    /// New data is available on X-axis
+    pub x_new: bool,
+}
+
+/// FIFO status
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FifoStatus {
+    /// The number of stored samples is equal to or higher than the watermark level
+    pub watermark: bool,
+    /// The FIFO is full and at least one sample has been overwritten
+    pub overrun: bool,
+    /// The FIFO is empty
+    pub empty: bool,
+    /// Number of unread samples (`FSS4-FSS0`)
+    pub stored: u8,
+}
+
+impl FifoStatus {
+    fn from_u8(from: u8) -> Self {
+        FifoStatus {
+            watermark: (from & FIFO_SRC_WTM) != 0,
+            overrun: (from & FIFO_SRC_OVRN) != 0,
+            empty: (from & FIFO_SRC_EMPTY) != 0,
+            stored: from & FIFO_SRC_FSS,
+        }
+    }
+}
+
+impl Status {
+    fn from_u8(from: u8) -> Self {
+        Status {
+            overrun: (from & (1 << 7)) != 0,
+            z_overrun: (from & (1 << 6)) != 0,
+            y_overrun: (from & (1 << 5)) != 0,
+            x_overrun: (from & (1 << 4)) != 0,
+            new_data: (from & (1 << 3)) != 0,
+            z_new: (from & (1 << 2)) != 0,
+            y_new: (from & (1 << 1)) != 0,
+            x_new: (from & 1) != 0,
+        }
+    }
+
+    /// Clear the bits of axes which are not enabled in `axes` (`CTRL_REG1`
+    /// layout) and recompute the aggregated flags from the enabled axes only
+    fn masked(self, axes: u8) -> Self {
+        let enabled = |axis: Axis| axes & axis.enable_bit() != 0;
+        let (x, y, z) = (enabled(Axis::X), enabled(Axis::Y), enabled(Axis::Z));
+        let mut status = Status {
+            z_overrun: self.z_overrun && z,
+            y_overrun: self.y_overrun && y,
+            x_overrun: self.x_overrun && x,
+            z_new: self.z_new && z,
+            y_new: self.y_new && y,
+            x_new: self.x_new && x,
+            ..self
+        };
+        status.overrun = status.x_overrun || status.y_overrun || status.z_overrun;
+        status.new_data = status.x_new || status.y_new || status.z_new;
+        status
+    }
+
+    /// Whether new data is available on every axis enabled in `axes`
+    /// (`CTRL_REG1` layout)
+    fn all_new(&self, axes: u8) -> bool {
+        (self.x_new || axes & Axis::X.enable_bit() == 0)
+            && (self.y_new || axes & Axis::Y.enable_bit() == 0)
+            && (self.z_new || axes & Axis::Z.enable_bit() == 0)
+    }
+}
+
+/// Snapshot of the configuration, data and interrupt registers
+///
+/// Decoded from the registers `CTRL_REG1` (0x20) through `INT1_DURATION`
+/// (0x38). Reading the snapshot from the sensor clears latched interrupts
+/// and the new data flags and consumes a FIFO sample, see
+/// `read_all_registers`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Registers {
+    /// Raw values of `CTRL_REG1` through `CTRL_REG5`
+    pub ctrl: [u8; 5],
+    /// Output Data Rate
+    pub odr: Odr,
+    /// Bandwidth
+    pub bandwidth: Bandwidth,
+    /// Full Scale Selection
+    pub scale: Scale,
+    /// Block data update enabled
+    pub bdu: bool,
+    /// `REFERENCE` register, `None` in `HighPassMode::NormalReset`, where
+    /// reading it would reset the high-pass filter
+    pub reference: Option<u8>,
+    /// Sensor status
+    pub status: Status,
+    /// Gyroscope and temperature measurements
+    pub measurements: Measurements,
+    /// FIFO mode of `FIFO_CTRL_REG`
+    pub fifo_mode: FifoMode,
+    /// FIFO watermark level of `FIFO_CTRL_REG`
+    pub fifo_watermark: u8,
+    /// FIFO status (`FIFO_SRC_REG`)
+    pub fifo_status: FifoStatus,
+    /// Interrupt 1 configuration (`INT1_CFG`)
+    pub int1_config: Int1Config,
+    /// Interrupt 1 source (`INT1_SRC`)
+    pub int1_source: Int1Source,
+    /// Interrupt thresholds for the X, Y and Z axis
+    pub int1_threshold: [u16; 3],
+    /// Interrupt 1 duration in samples
+    pub int1_duration: u8,
+    /// `WAIT` bit of `INT1_DURATION`
+    pub int1_wait: bool,
+}
+
+impl Registers {
+    /// Number of registers in the snapshot
+    pub const LEN: usize = 25;
+
+    /// Offset of `REFERENCE` from `CTRL_REG1`
+    const REFERENCE: usize = 5;
+
+    /// Offset of `OUT_TEMP` from `CTRL_REG1`
+    const OUT_TEMP: usize = 6;
+
+    /// Offset of `FIFO_CTRL_REG` from `CTRL_REG1`
+    const FIFO_CTRL: usize = 14;
+
+    /// Decode a register image starting at `CTRL_REG1`
+    ///
+    /// The `REFERENCE` byte is ignored in `HighPassMode::NormalReset`.
+    pub fn decode(image: &[u8; Registers::LEN]) -> Self {
+        let threshold = |h: u8, l: u8| (((h & 0x7F) as u16) << 8) | l as u16;
+        Registers {
+            ctrl: [image[0], image[1], image[2], image[3], image[4]],
+            odr: Odr::from_u8(image[0]),
+            bandwidth: Bandwidth::from_u8(image[0]),
+            scale: Scale::from_u8(image[3]),
+            bdu: (image[3] & CTRL_REG4_BDU) != 0,
+            reference: DeviceState::saves_reference(image[1]).then_some(image[5]),
+            status: Status::from_u8(image[7]),
+            measurements: Measurements {
+                gyro: I16x3::from_bytes_ordered(&image[8..14], image[3] & CTRL_REG4_BLE != 0),
+                temp_raw: image[6] as i8,
+            },
+            fifo_mode: FifoMode::from_u8(image[14]),
+            fifo_watermark: image[14] & FIFO_CTRL_WTM,
+            fifo_status: FifoStatus::from_u8(image[15]),
+            int1_config: Int1Config::from_u8(image[16]),
+            int1_source: Int1Source::from_u8(image[17]),
+            int1_threshold: [
+                threshold(image[18], image[19]),
+                threshold(image[20], image[21]),
+                threshold(image[22], image[23]),
+            ],
+            int1_duration: image[24] & INT1_DURATION_D,
+            int1_wait: image[24] & INT1_DURATION_WAIT != 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
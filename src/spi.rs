@@ -1,10 +1,16 @@
+use embedded_hal::digital::InputPin;
 use embedded_hal::spi::Mode;
 
+use super::gyroscope::Gyroscope;
 use super::{bisync, only_async, only_sync};
 
+#[only_sync]
+use embedded_hal::delay::DelayNs;
 #[only_sync]
 use embedded_hal::spi::SpiDevice;
 #[only_async]
+use embedded_hal_async::delay::DelayNs;
+#[only_async]
 use embedded_hal_async::spi::SpiDevice;
 
 use crate::*;
@@ -20,50 +26,340 @@ const SINGLE: u8 = 0 << 6;
 /// L3GD20 driver
 pub struct L3gd20<Spi> {
     spi: Spi,
+    read_mode: ReadMode,
+    idle_count: u16,
+    axes: u8,
+    status_masking: bool,
+    invert: [bool; 3],
+    last_status: Status,
+    bias: Bias,
+    dirty: bool,
+    angles: F32x3,
+    scale: Scale,
+    thermal: ThermalModel,
+    big_endian: bool,
+    scale_correction: [f32; 3],
+    decimation: u8,
+    mount: [[f32; 3]; 3],
+    temp_offset: i16,
 }
 
 #[bisync]
 impl<Spi: SpiDevice> L3gd20<Spi> {
-    /// Creates a new driver from a SPI peripheral and a NCS pin
+    /// Creates a new driver from a SPI device
+    ///
+    /// `spi` must implement `embedded_hal::spi::SpiDevice` (blocking) or
+    /// `embedded_hal_async::spi::SpiDevice` (asynchronous), which also manages
+    /// the chip select pin. The bus must be configured for `MODE`. For a sensor
+    /// connected via I2C, use `i2c::L3gd20` instead.
+    ///
+    /// Passing an I2C bus instead fails to compile because it does not
+    /// implement `SpiDevice`:
+    ///
+    /// ```compile_fail
+    /// # use embedded_hal::i2c::{ErrorType, I2c, Operation};
+    /// # struct I2cBus;
+    /// # impl ErrorType for I2cBus {
+    /// #     type Error = core::convert::Infallible;
+    /// # }
+    /// # impl I2c for I2cBus {
+    /// #     fn transaction(&mut self, _: u8, _: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+    /// #         Ok(())
+    /// #     }
+    /// # }
+    /// let l3gd20 = l3gd20::spi::L3gd20::new(I2cBus);
+    /// ```
+    ///
+    /// Reads `WHO_AM_I` before powering up the sensor and returns
+    /// `Error::InvalidWhoAmI` if it matches neither the L3GD20 nor the
+    /// L3GD20H, e.g. because of a wiring mistake. See `new_unchecked` to skip
+    /// this check.
     #[bisync]
-    pub async fn new(spi: Spi) -> Result<Self, Spi::Error> {
-        let mut l3gd20 = L3gd20 { spi };
+    pub async fn new(spi: Spi) -> Result<Self, Error<Spi::Error>> {
+        let mut l3gd20 = Self::unpowered(spi);
+        l3gd20.model().await?;
+        l3gd20.power_up_defaults().await?;
 
-        // power up and enable all the axes
-        l3gd20
-            .write_register(Register::CTRL_REG1, 0b0000_1111)
-            .await?;
+        Ok(l3gd20)
+    }
+
+    /// Creates a new driver without checking `WHO_AM_I`
+    ///
+    /// Same as `new`, but skips the probe for boards known to carry the
+    /// sensor.
+    #[bisync]
+    pub async fn new_unchecked(spi: Spi) -> Result<Self, Spi::Error> {
+        let mut l3gd20 = Self::unpowered(spi);
+        l3gd20.power_up_defaults().await?;
 
         Ok(l3gd20)
     }
 
+    /// Driver state before the sensor is powered up
+    fn unpowered(spi: Spi) -> Self {
+        L3gd20 {
+            spi,
+            read_mode: ReadMode::default(),
+            idle_count: 0,
+            axes: CTRL_REG1_AXES,
+            status_masking: false,
+            invert: [false; 3],
+            last_status: Status::from_u8(0),
+            bias: Bias::default(),
+            dirty: true,
+            angles: F32x3::default(),
+            scale: Scale::Dps250,
+            thermal: ThermalModel::default(),
+            big_endian: false,
+            scale_correction: [1.0; 3],
+            decimation: 1,
+            mount: IDENTITY,
+            temp_offset: TEMP_OFFSET_DEFAULT,
+        }
+    }
+
+    /// Power up with the default output data rate and bandwidth, enable the
+    /// selected axes and read the configured byte order and scale
+    #[bisync]
+    async fn power_up_defaults(&mut self) -> Result<(), Spi::Error> {
+        self.write_register(Register::CTRL_REG1, CTRL_REG1_PD | self.axes)
+            .await?;
+        // Pick up the byte order and scale in case the sensor was configured
+        // before
+        let reg4 = self.read_register(Register::CTRL_REG4).await?;
+        self.track_ctrl_reg4(reg4);
+        Ok(())
+    }
+
+    /// Reboot the memory content of the sensor
+    ///
+    /// Sets the `BOOT` bit of `CTRL_REG5`, which reloads the trimming values
+    /// and resets the sensor, e.g. to recover after a brownout. Waits
+    /// `BOOT_TIME_US` before powering up the sensor again with the same
+    /// defaults as `new` and the axes selected in the driver. Any other
+    /// configuration must be applied again afterwards. The sensor must not be
+    /// accessed until this returns.
+    #[bisync]
+    pub async fn reboot(&mut self, delay: &mut impl DelayNs) -> Result<(), Spi::Error> {
+        let reg5 = self.read_register(Register::CTRL_REG5).await?;
+        self.write_register(Register::CTRL_REG5, reg5 | CTRL_REG5_BOOT)
+            .await?;
+        delay.delay_us(BOOT_TIME_US).await;
+        self.power_up_defaults().await
+    }
+
+    /// Creates a new driver and applies `config`
+    ///
+    /// Checks `WHO_AM_I` like `new`, then writes `CTRL_REG1` to `CTRL_REG5`
+    /// in one burst, without reading them first. Returns the driver together
+    /// with the configuration effectively applied, which differs from
+    /// `config` if a setting was clamped. See `new` for the requirements on
+    /// the bus.
+    #[bisync]
+    pub async fn new_with_config(
+        spi: Spi,
+        config: Config,
+    ) -> Result<(Self, Config), Error<Spi::Error>> {
+        let config = config.clamped();
+        let mut l3gd20 = Self::unpowered(spi);
+        l3gd20.model().await?;
+        l3gd20.apply_descriptor(&config.registers()).await?;
+        Ok((l3gd20, config))
+    }
+
+    /// Configure the sensor for a target noise density
+    ///
+    /// Picks the output data rate, bandwidth and scale achieving a noise
+    /// density of at most `target_dps_per_sqrt_hz` in dps/√Hz, including the
+    /// quantization noise, or the closest configuration if the target is
+    /// below what the sensor achieves. Prefers the largest range, then the
+    /// highest output data rate and bandwidth. The other settings and the
+    /// power mode are kept. Returns the applied configuration.
+    #[bisync]
+    pub async fn configure_for_noise_density(
+        &mut self,
+        target_dps_per_sqrt_hz: f32,
+    ) -> Result<Config, Spi::Error> {
+        let current = self.config().await?;
+        let config = Config::for_noise_density(target_dps_per_sqrt_hz, current);
+        self.apply_config(config).await
+    }
+
+    /// Read the current configuration
+    ///
+    /// Decodes `CTRL_REG1` to `CTRL_REG5`, read in one burst.
+    #[bisync]
+    pub async fn config(&mut self) -> Result<Config, Spi::Error> {
+        Ok(Config::from_registers(&self.config_descriptor().await?))
+    }
+
+    /// Apply the output data rate, bandwidth, scale and axes of `config` and
+    /// return the configuration effectively applied
+    ///
+    /// The power mode is preserved. The axes become the ones enabled on
+    /// wake-up if the sensor sleeps or is powered down, and an empty axis
+    /// selection keeps the current axes.
+    #[bisync]
+    async fn apply_config(&mut self, config: Config) -> Result<Config, Spi::Error> {
+        let config = config.clamped();
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        let axes = config.axes_enable_bits();
+        if axes != 0 {
+            self.axes = axes;
+        }
+        let mode = PowerMode::from_u8(reg1);
+        let reg1 = (config.ctrl_reg1() & !(CTRL_REG1_PD | CTRL_REG1_AXES)) | mode.to_u8(self.axes);
+        self.write_register(Register::CTRL_REG1, reg1).await?;
+        self.set_scale(config.scale).await?;
+        Ok(Config {
+            axes: Config::axes_from_enable_bits(self.axes),
+            ..config
+        })
+    }
+
     /// Temperature measurement + gyroscope measurements
     #[bisync]
     pub async fn all(&mut self) -> Result<Measurements, Spi::Error> {
+        Ok(self.all_detailed().await?.0)
+    }
+
+    /// Temperature measurement + gyroscope measurements in physical units
+    ///
+    /// Reads the measurements in one burst like `all` and converts the
+    /// gyroscope measurements like `gyro_dps`, with the cached scale.
+    #[bisync]
+    pub async fn all_scaled(&mut self) -> Result<ScaledMeasurements, Spi::Error> {
+        let measurements = self.all().await?;
+        Ok(ScaledMeasurements {
+            gyro: self.rate_dps(self.scale, measurements.gyro).to_array(),
+            temp_c: measurements.temp_celcius_with_offset(self.temp_offset),
+        })
+    }
+
+    /// Temperature measurement + gyroscope measurements + status
+    ///
+    /// `STATUS_REG` lies between `OUT_TEMP` and the gyroscope output
+    /// registers, so it is decoded from the same burst read as `all`. Status
+    /// masking applies, see `set_status_masking`
+    #[bisync]
+    pub async fn all_detailed(&mut self) -> Result<(Measurements, Status), Spi::Error> {
         let mut bytes = [0u8; 9];
         self.read_many(Register::OUT_TEMP, &mut bytes).await?;
 
-        Ok(Measurements {
-            gyro: I16x3 {
-                x: (bytes[3] as u16 + ((bytes[4] as u16) << 8)) as i16,
-                y: (bytes[5] as u16 + ((bytes[6] as u16) << 8)) as i16,
-                z: (bytes[7] as u16 + ((bytes[8] as u16) << 8)) as i16,
-            },
+        let status = self.decode_status(bytes[2]);
+        let measurements = Measurements {
+            gyro: self.correct(self.decode(&bytes[3..])),
             temp_raw: bytes[1] as i8,
-        })
+        };
+        Ok((measurements, status))
     }
 
     /// Gyroscope measurements
+    ///
+    /// The bias set with `set_bias` is subtracted and the sign convention set
+    /// with `set_sign_convention` is applied.
     #[bisync]
     pub async fn gyro(&mut self) -> Result<I16x3, Spi::Error> {
+        let raw = self.gyro_uncorrected().await?;
+        Ok(self.correct(raw))
+    }
+
+    /// Gyroscope measurements at the decimated rate
+    ///
+    /// Waits for new data as many times as the decimation factor, see
+    /// `set_decimation`, reading and discarding the samples in between, and
+    /// returns the last sample corrected like `gyro`. Returns
+    /// `Error::Timeout` if a wait times out, see `wait_for_data`.
+    #[bisync]
+    pub async fn gyro_decimated(&mut self) -> Result<I16x3, Error<Spi::Error>> {
+        for _ in 1..self.decimation {
+            self.wait_for_data().await?;
+            self.gyro_uncorrected().await?;
+        }
+        self.wait_for_data().await?;
+        Ok(self.gyro().await?)
+    }
+
+    /// Whether new data is available on any axis
+    ///
+    /// Reads `STATUS_REG` and returns `Status::new_data`. Status masking
+    /// applies, see `set_status_masking`.
+    #[bisync]
+    pub async fn data_ready(&mut self) -> Result<bool, Spi::Error> {
+        Ok(self.status().await?.new_data)
+    }
+
+    /// Poll `STATUS_REG` until new data is available on any axis
+    ///
+    /// Returns `Error::Timeout` if no new data is available within
+    /// `DATA_READY_POLLS` polls, e.g. because the sensor is powered down,
+    /// asleep or has all axes disabled.
+    #[only_sync]
+    pub fn wait_for_data(&mut self) -> Result<(), Error<Spi::Error>> {
+        for _ in 0..DATA_READY_POLLS {
+            if self.data_ready()? {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Poll `STATUS_REG` until new data is available on any axis
+    ///
+    /// Yields to the executor between polls. Returns `Error::Timeout` if no
+    /// new data is available within `DATA_READY_POLLS` polls, e.g. because
+    /// the sensor is powered down, asleep or has all axes disabled.
+    #[only_async]
+    pub async fn wait_for_data(&mut self) -> Result<(), Error<Spi::Error>> {
+        for _ in 0..DATA_READY_POLLS {
+            if self.data_ready().await? {
+                return Ok(());
+            }
+            YieldNow::default().await;
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Get the software decimation factor applied by `gyro_decimated`
+    pub fn decimation(&self) -> u8 {
+        self.decimation
+    }
+
+    /// Set the software decimation factor applied by `gyro_decimated`
+    ///
+    /// `gyro_decimated` then returns every `factor`th sample and discards the others,
+    /// which gives rates below the lowest output data rate without changing
+    /// the sensor configuration. A factor of 0 is treated as 1, i.e. no
+    /// decimation, which is the default.
+    pub fn set_decimation(&mut self, factor: u8) -> &mut Self {
+        self.decimation = factor.max(1);
+        self
+    }
+
+    /// Rate in Hz of the samples returned by `gyro_decimated`, i.e. the output data
+    /// rate divided by the decimation factor
+    #[bisync]
+    pub async fn effective_odr(&mut self) -> Result<f32, Spi::Error> {
+        Ok(self.odr().await?.hz() / self.decimation as f32)
+    }
+
+    /// Gyroscope measurements without bias and sign correction
+    ///
+    /// Uses one burst read, or six single register reads if
+    /// `ReadMode::SingleByte` is selected.
+    #[bisync]
+    async fn gyro_uncorrected(&mut self) -> Result<I16x3, Spi::Error> {
         let mut bytes = [0u8; 7];
-        self.read_many(Register::OUT_X_L, &mut bytes).await?;
+        match self.read_mode {
+            ReadMode::Burst => self.read_many(Register::OUT_X_L, &mut bytes).await?,
+            ReadMode::SingleByte => {
+                for (byte, reg) in bytes[1..].iter_mut().zip(GYRO_OUT_REGISTERS) {
+                    *byte = self.read_register(reg).await?;
+                }
+            }
+        }
 
-        Ok(I16x3 {
-            x: (bytes[1] as u16 + ((bytes[2] as u16) << 8)) as i16,
-            y: (bytes[3] as u16 + ((bytes[4] as u16) << 8)) as i16,
-            z: (bytes[5] as u16 + ((bytes[6] as u16) << 8)) as i16,
-        })
+        Ok(self.decode(&bytes[1..]))
     }
 
     /// Raw temperature sensor measurement
@@ -72,10 +368,27 @@ impl<Spi: SpiDevice> L3gd20<Spi> {
         Ok(self.read_register(Register::OUT_TEMP).await? as i8)
     }
 
-    /// Actual temperature derived by subtracting the raw measurement to the baseline value of 25 C
+    /// Actual temperature derived by subtracting the raw measurement from the
+    /// reference set with `set_temp_offset`
     #[bisync]
     pub async fn temp_celcius(&mut self) -> Result<i16, Spi::Error> {
-        Ok(25 - self.temp_raw().await? as i16)
+        Ok(self.temp_offset - self.temp_raw().await? as i16)
+    }
+
+    /// Get the temperature reference in degrees celcius
+    pub fn temp_offset(&self) -> i16 {
+        self.temp_offset
+    }
+
+    /// Set the temperature reference in degrees celcius
+    ///
+    /// The temperature sensor is uncalibrated. The temperature is computed as
+    /// `offset - temp_raw`, e.g. with an offset measured at a known
+    /// temperature. Used by all methods of the driver which convert the
+    /// temperature. The default is `TEMP_OFFSET_DEFAULT`.
+    pub fn set_temp_offset(&mut self, offset: i16) -> &mut Self {
+        self.temp_offset = offset;
+        self
     }
 
     /// Reads the WHO_AM_I register; should return `0xD4`
@@ -85,10 +398,26 @@ impl<Spi: SpiDevice> L3gd20<Spi> {
     }
 
     /// Read `STATUS_REG` of sensor
+    ///
+    /// If status masking is enabled, the bits of disabled axes are cleared.
+    /// See `set_status_masking`. Also updates the cached status, see
+    /// `cached_status`
     #[bisync]
     pub async fn status(&mut self) -> Result<Status, Spi::Error> {
         let sts = self.read_register(Register::STATUS_REG).await?;
-        Ok(Status::from_u8(sts))
+        Ok(self.decode_status(sts))
+    }
+
+    /// Whether new data is available on all axes
+    ///
+    /// If status masking is enabled, only the enabled axes are considered.
+    #[bisync]
+    pub async fn all_new(&mut self) -> Result<bool, Spi::Error> {
+        let sts = self.status().await?;
+        if self.status_masking {
+            return Ok(sts.all_new(self.axes));
+        }
+        Ok(sts.all_new(CTRL_REG1_AXES))
     }
 
     /// Get the current Output Data Rate
@@ -105,6 +434,54 @@ impl<Spi: SpiDevice> L3gd20<Spi> {
         self.change_config(Register::CTRL_REG1, odr).await
     }
 
+    /// Get the low output data rate of the L3GD20H
+    ///
+    /// Returns `None` if the standard output data rates are selected or the
+    /// sensor is an L3GD20, which does not support the low rates.
+    #[bisync]
+    pub async fn low_odr(&mut self) -> Result<Option<LowOdr>, Error<Spi::Error>> {
+        if self.model().await? != Model::L3gd20h {
+            return Ok(None);
+        }
+        if self.read_register(Register::LOW_ODR).await? & LOW_ODR_EN == 0 {
+            return Ok(None);
+        }
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        Ok(Some(LowOdr::from_u8(reg1)))
+    }
+
+    /// Select a low output data rate of the L3GD20H
+    ///
+    /// Returns `Error::Unsupported` on the L3GD20. `odr` and the methods
+    /// which time measurements by the output data rate report and use the
+    /// standard rate selected by the same `DR1-DR0` bits, so pass explicit
+    /// periods while a low rate is selected. Use `clear_low_odr` to return to
+    /// the standard rates.
+    #[bisync]
+    pub async fn set_low_odr(&mut self, odr: LowOdr) -> Result<&mut Self, Error<Spi::Error>> {
+        if self.model().await? != Model::L3gd20h {
+            return Err(Error::Unsupported);
+        }
+        self.change_config(Register::CTRL_REG1, odr).await?;
+        let low_odr = self.read_register(Register::LOW_ODR).await?;
+        self.write_register(Register::LOW_ODR, low_odr | LOW_ODR_EN)
+            .await?;
+        Ok(self)
+    }
+
+    /// Return to the standard output data rates
+    ///
+    /// Clears the `Low_ODR` bit on the L3GD20H, does nothing on the L3GD20.
+    #[bisync]
+    pub async fn clear_low_odr(&mut self) -> Result<&mut Self, Error<Spi::Error>> {
+        if self.model().await? == Model::L3gd20h {
+            let low_odr = self.read_register(Register::LOW_ODR).await?;
+            self.write_register(Register::LOW_ODR, low_odr & !LOW_ODR_EN)
+                .await?;
+        }
+        Ok(self)
+    }
+
     /// Get current Bandwidth
     #[bisync]
     pub async fn bandwidth(&mut self) -> Result<Bandwidth, Spi::Error> {
@@ -129,11 +506,31 @@ impl<Spi: SpiDevice> L3gd20<Spi> {
         Ok(Scale::from_u8(scl))
     }
 
-    /// Returns a mutable reference to the I2C peripheral used to drive this device.
+    /// Get the mode used for multi-byte measurement reads
+    pub fn read_mode(&self) -> ReadMode {
+        self.read_mode
+    }
+
+    /// Set the mode used for multi-byte measurement reads
+    ///
+    /// `ReadMode::Burst` is the default. See `ReadMode` for further explanation
+    pub fn set_read_mode(&mut self, mode: ReadMode) -> &mut Self {
+        self.read_mode = mode;
+        self
+    }
+
+    /// Returns a mutable reference to the SPI peripheral used to drive this device.
     pub fn spi(&mut self) -> &mut Spi {
         &mut self.spi
     }
 
+    /// Destroys the driver and returns the SPI peripheral
+    ///
+    /// The sensor keeps its current configuration.
+    pub fn release(self) -> Spi {
+        self.spi
+    }
+
     /// Set the Full Scale Selection
     ///
     /// This sets the sensitivity of the sensor, see `Scale` for more
@@ -143,59 +540,2407 @@ impl<Spi: SpiDevice> L3gd20<Spi> {
         self.change_config(Register::CTRL_REG4, scale).await
     }
 
+    /// Get the current FIFO mode
     #[bisync]
-    pub async fn read_register(&mut self, reg: Register) -> Result<u8, Spi::Error> {
-        let mut buffer = [reg.addr() | SINGLE | READ, 0];
-        self.spi.transfer_in_place(&mut buffer).await?;
-
-        Ok(buffer[1])
+    pub async fn fifo_mode(&mut self) -> Result<FifoMode, Spi::Error> {
+        let ctrl = self.read_register(Register::FIFO_CTRL_REG).await?;
+        Ok(FifoMode::from_u8(ctrl))
     }
 
-    /// Read multiple bytes starting from the `start_reg` register.
-    /// This function will attempt to fill the provided buffer.
+    /// Set the FIFO mode
+    ///
+    /// Also sets the `FIFO_EN` bit of `CTRL_REG5` for any mode other than
+    /// `FifoMode::Bypass`, and clears it for `FifoMode::Bypass`. The watermark
+    /// level is preserved. Switching to `FifoMode::Bypass` discards the
+    /// samples in the FIFO.
     #[bisync]
-    pub async fn read_many(
-        &mut self,
-        start_reg: Register,
-        buffer: &mut [u8],
-    ) -> Result<(), Spi::Error> {
-        buffer[0] = start_reg.addr() | MULTI | READ;
-        self.spi.transfer_in_place(buffer).await?;
+    pub async fn set_fifo_mode(&mut self, mode: FifoMode) -> Result<&mut Self, Spi::Error> {
+        self.change_config(Register::FIFO_CTRL_REG, mode).await?;
+        let reg5 = self.read_register(Register::CTRL_REG5).await?;
+        let reg5 = if mode == FifoMode::Bypass {
+            reg5 & !CTRL_REG5_FIFO_EN
+        } else {
+            reg5 | CTRL_REG5_FIFO_EN
+        };
+        self.write_register(Register::CTRL_REG5, reg5).await?;
+        Ok(self)
+    }
 
-        Ok(())
+    /// Set the self-test mode
+    #[bisync]
+    pub async fn set_self_test(&mut self, mode: SelfTest) -> Result<&mut Self, Spi::Error> {
+        self.change_config(Register::CTRL_REG4, mode).await
     }
 
+    /// Get the current high-pass filter mode
     #[bisync]
-    pub async fn write_register(&mut self, reg: Register, byte: u8) -> Result<(), Spi::Error> {
-        let buffer = [reg.addr() | SINGLE | WRITE, byte];
-        self.spi.write(&buffer).await?;
+    pub async fn high_pass_mode(&mut self) -> Result<HighPassMode, Spi::Error> {
+        let reg2 = self.read_register(Register::CTRL_REG2).await?;
+        Ok(HighPassMode::from_u8(reg2))
+    }
 
-        Ok(())
+    /// Get the current high-pass filter cutoff configuration
+    #[bisync]
+    pub async fn high_pass_cutoff(&mut self) -> Result<HighPassCutoff, Spi::Error> {
+        let reg2 = self.read_register(Register::CTRL_REG2).await?;
+        Ok(HighPassCutoff::from_u8(reg2))
     }
 
-    /// Change configuration in register
+    /// Set the high-pass filter mode and cutoff configuration
     ///
-    /// Helper function to update a particular part of a register without
-    /// affecting other parts of the register that might contain desired
-    /// configuration. This allows the `L3gd20` struct to be used like
-    /// a builder interface when configuring specific parameters.
+    /// Both are written to `CTRL_REG2` at once. This does not enable the
+    /// filter, see `set_high_pass_enabled` and `set_out_select`.
     #[bisync]
-    pub async fn change_config<B: BitValue>(
+    pub async fn set_high_pass(
         &mut self,
-        reg: Register,
-        bits: B,
+        mode: HighPassMode,
+        cutoff: HighPassCutoff,
     ) -> Result<&mut Self, Spi::Error> {
-        // Create bit mask from width and shift of value
-        let mask = B::mask() << B::shift();
-        // Extract the value as u8
-        let bits = (bits.value() << B::shift()) & mask;
-        // Read current value of register
-        let current = self.read_register(reg).await?;
-        // Use supplied mask so we don't affect more than necessary
-        let masked = current & !mask;
-        // Use `or` to apply the new value without affecting other parts
-        let new_reg = masked | bits;
-        self.write_register(reg, new_reg).await?;
+        let mask = HighPassMode::mask() << HighPassMode::shift()
+            | HighPassCutoff::mask() << HighPassCutoff::shift();
+        let bits =
+            mode.value() << HighPassMode::shift() | cutoff.value() << HighPassCutoff::shift();
+        let reg2 = self.read_register(Register::CTRL_REG2).await?;
+        self.write_register(Register::CTRL_REG2, reg2 & !mask | bits)
+            .await?;
+        Ok(self)
+    }
+
+    /// Get the high-pass filter reference value
+    ///
+    /// In `HighPassMode::NormalReset` reading this register resets the
+    /// filter.
+    #[bisync]
+    pub async fn reference(&mut self) -> Result<u8, Spi::Error> {
+        self.read_register(Register::REFERENCE).await
+    }
+
+    /// Set the high-pass filter reference value
+    ///
+    /// In `HighPassMode::Reference` the value is subtracted from the output,
+    /// e.g. to remove a known bias in hardware.
+    #[bisync]
+    pub async fn set_reference(&mut self, val: u8) -> Result<&mut Self, Spi::Error> {
+        self.write_register(Register::REFERENCE, val).await?;
+        Ok(self)
+    }
+
+    /// Check whether the high-pass filter is enabled
+    #[bisync]
+    pub async fn high_pass_enabled(&mut self) -> Result<bool, Spi::Error> {
+        Ok(self.read_register(Register::CTRL_REG5).await? & CTRL_REG5_HPEN != 0)
+    }
+
+    /// Enable or disable the high-pass filter
+    ///
+    /// Sets the `HPen` bit of `CTRL_REG5`. The filtered data only reaches the
+    /// output registers if `set_out_select` selects a high-pass path.
+    #[bisync]
+    pub async fn set_high_pass_enabled(&mut self, enabled: bool) -> Result<&mut Self, Spi::Error> {
+        let reg5 = self.read_register(Register::CTRL_REG5).await?;
+        let reg5 = if enabled {
+            reg5 | CTRL_REG5_HPEN
+        } else {
+            reg5 & !CTRL_REG5_HPEN
+        };
+        self.write_register(Register::CTRL_REG5, reg5).await?;
+        Ok(self)
+    }
+
+    /// Get the current output path selection
+    #[bisync]
+    pub async fn out_select(&mut self) -> Result<OutSelect, Spi::Error> {
+        let reg5 = self.read_register(Register::CTRL_REG5).await?;
+        Ok(OutSelect::from_u8(reg5))
+    }
+
+    /// Set the output path selection
+    #[bisync]
+    pub async fn set_out_select(&mut self, out: OutSelect) -> Result<&mut Self, Spi::Error> {
+        self.change_config(Register::CTRL_REG5, out).await
+    }
+
+    /// Run self-test 0 and return the change of the output
+    ///
+    /// Must be called with the sensor at rest. Reads a baseline measurement,
+    /// enables `SelfTest::Mode0`, waits `SETTLING_PERIODS` output data
+    /// periods and reads again. Self-test is disabled afterwards. Convert the
+    /// returned difference with the current `Scale` and compare it to the
+    /// acceptance range in the data sheet.
+    #[bisync]
+    pub async fn self_test(&mut self, delay: &mut impl DelayNs) -> Result<I16x3, Spi::Error> {
+        let period_us = self.odr().await?.period_us();
+        let baseline = self.gyro_uncorrected().await?;
+
+        self.set_self_test(SelfTest::Mode0).await?;
+        delay.delay_us(SETTLING_PERIODS * period_us).await;
+        let result = self.gyro_uncorrected().await;
+        self.set_self_test(SelfTest::Disabled).await?;
+        let active = result?;
+
+        Ok(I16x3 {
+            x: active.x.saturating_sub(baseline.x),
+            y: active.y.saturating_sub(baseline.y),
+            z: active.z.saturating_sub(baseline.z),
+        })
+    }
+
+    /// Power-on self test
+    ///
+    /// Must be called with the sensor at rest. Runs the following checks and
+    /// reports each result instead of stopping at the first failure:
+    ///
+    /// - `WHO_AM_I` matches a known model
+    /// - test patterns written to `INT1_TSH_XL` read back unchanged, the
+    ///   register is restored afterwards
+    /// - `self_test` changes the output of every axis by 50 % to 150 % of
+    ///   `Scale::self_test_dps`
+    /// - the variance of `POST_NOISE_SAMPLES` measurements, one per output
+    ///   data period, is within three standard deviations of the noise of
+    ///   `gyro_measurement`
+    #[bisync]
+    pub async fn post(&mut self, delay: &mut impl DelayNs) -> Result<PostReport, Spi::Error> {
+        let model = Model::from_who_am_i(self.who_am_i().await?);
+
+        let saved = self.read_register(Register::INT1_TSH_XL).await?;
+        let mut loopback = true;
+        for pattern in LOOPBACK_PATTERNS {
+            self.write_register(Register::INT1_TSH_XL, pattern).await?;
+            loopback &= self.read_register(Register::INT1_TSH_XL).await? == pattern;
+        }
+        self.write_register(Register::INT1_TSH_XL, saved).await?;
+
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        let scale = self.scale;
+        let odr = Odr::from_u8(reg1);
+        let period_us = odr.period_us();
+
+        let self_test = self.self_test(delay).await?;
+        let expected = scale.self_test_dps();
+        let plausible = |change: i16| {
+            let change = scale.degrees(change).abs();
+            change >= expected / 2.0 && change <= expected * 1.5
+        };
+        let self_test_passed =
+            plausible(self_test.x) && plausible(self_test.y) && plausible(self_test.z);
+
+        // Let the output settle after the self-test
+        delay.delay_us(SETTLING_PERIODS * period_us).await;
+        let mut window = VarWindow::<POST_NOISE_SAMPLES>::new();
+        for _ in 0..POST_NOISE_SAMPLES {
+            delay.delay_us(period_us).await;
+            window.push(self.gyro_uncorrected().await?);
+        }
+        let var = window.variance();
+        let lsb2 = scale.dps_per_lsb() * scale.dps_per_lsb();
+        let noise_variance = F32x3 {
+            x: var.x * lsb2,
+            y: var.y * lsb2,
+            z: var.z * lsb2,
+        };
+        let limit = 9.0 * Measurement::variance(odr, Bandwidth::from_u8(reg1), scale);
+        let noise_passed =
+            noise_variance.x <= limit && noise_variance.y <= limit && noise_variance.z <= limit;
+
+        Ok(PostReport {
+            model,
+            loopback,
+            self_test,
+            self_test_passed,
+            noise_variance,
+            noise_passed,
+        })
+    }
+
+    /// Whether block data update is enabled
+    #[bisync]
+    pub async fn block_data_update(&mut self) -> Result<bool, Spi::Error> {
+        Ok(self.read_register(Register::CTRL_REG4).await? & CTRL_REG4_BDU != 0)
+    }
+
+    /// Enable or disable block data update
+    ///
+    /// Sets or clears the `BDU` bit of `CTRL_REG4`. When enabled, the output
+    /// registers are not updated until both bytes of a sample have been read,
+    /// so a read never mixes the high and low bytes of different samples.
+    /// Recommended for any polling loop which does not use the FIFO. Disabled
+    /// by default.
+    #[bisync]
+    pub async fn set_block_data_update(&mut self, enabled: bool) -> Result<&mut Self, Spi::Error> {
+        let reg4 = self.read_register(Register::CTRL_REG4).await?;
+        let reg4 = if enabled {
+            reg4 | CTRL_REG4_BDU
+        } else {
+            reg4 & !CTRL_REG4_BDU
+        };
+        self.write_register(Register::CTRL_REG4, reg4).await?;
+        Ok(self)
+    }
+
+    /// Whether the output registers hold the high byte first
+    ///
+    /// Tracks the `BLE` bit of `CTRL_REG4`, which is read on construction
+    /// and updated on every write.
+    pub fn big_endian(&self) -> bool {
+        self.big_endian
+    }
+
+    /// Select the byte order of the output registers
+    ///
+    /// Sets (`big`) or clears the `BLE` bit of `CTRL_REG4`. The measurements
+    /// are decoded in the selected order, so this only matters for other
+    /// readers of the register map. Little endian by default.
+    #[bisync]
+    pub async fn set_big_endian(&mut self, big: bool) -> Result<&mut Self, Spi::Error> {
+        let reg4 = self.read_register(Register::CTRL_REG4).await?;
+        let reg4 = if big {
+            reg4 | CTRL_REG4_BLE
+        } else {
+            reg4 & !CTRL_REG4_BLE
+        };
+        self.write_register(Register::CTRL_REG4, reg4).await?;
         Ok(self)
     }
+
+    /// Verify the configured scale against a known angular rate
+    ///
+    /// Averages `SCALE_VERIFICATION_SAMPLES` measurements of `axis`, one per
+    /// output data period, while the sensor rotates at the constant
+    /// `reference_dps` (e.g. on a turntable). Returns whether the mean rate
+    /// converted with the current `Scale` is within `tolerance` degrees per
+    /// second of the reference.
+    #[bisync]
+    pub async fn verify_scale(
+        &mut self,
+        reference_dps: f32,
+        axis: Axis,
+        tolerance: f32,
+        delay: &mut impl DelayNs,
+    ) -> Result<bool, Spi::Error> {
+        let period_us = self.odr().await?.period_us();
+
+        let mut sum = 0i32;
+        for _ in 0..SCALE_VERIFICATION_SAMPLES {
+            delay.delay_us(period_us).await;
+            sum += self.gyro().await?.axis(axis) as i32;
+        }
+        let mean = sum as f32 / SCALE_VERIFICATION_SAMPLES as f32;
+
+        Ok((mean * self.scale.dps_per_lsb() - reference_dps).abs() <= tolerance)
+    }
+
+    /// Gyroscope measurements in degrees per second, in X, Y, Z order
+    ///
+    /// Corrected like `gyro`, then converted with the configured `Scale`,
+    /// the scale factor correction and the mount matrix. The scale is cached
+    /// by the driver, so this takes a single read.
+    #[bisync]
+    pub async fn gyro_dps(&mut self) -> Result<[f32; 3], Spi::Error> {
+        let gyro = self.gyro().await?;
+        Ok(self.rate_dps(self.scale, gyro).to_array())
+    }
+
+    /// Gyroscope measurements in radians per second, in X, Y, Z order
+    ///
+    /// Same as `gyro_dps`, converted to radians.
+    #[bisync]
+    pub async fn gyro_rad(&mut self) -> Result<[f32; 3], Spi::Error> {
+        Ok(self.gyro_dps().await?.map(f32::to_radians))
+    }
+
+    /// Gyroscope measurement with its expected variance
+    ///
+    /// The variance follows from `RATE_NOISE_DENSITY` over the configured
+    /// bandwidth and from the resolution of the configured `Scale`. The rate
+    /// is converted like in `gyro_dps`.
+    #[bisync]
+    pub async fn gyro_measurement(&mut self) -> Result<Measurement, Spi::Error> {
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        let scale = self.scale;
+        let gyro = self.gyro().await?;
+
+        let var = Measurement::variance(Odr::from_u8(reg1), Bandwidth::from_u8(reg1), scale);
+        Ok(Measurement {
+            rate: self.rate_dps(scale, gyro),
+            variance: F32x3 {
+                x: var,
+                y: var,
+                z: var,
+            },
+        })
+    }
+
+    /// Measure the time the output needs to recover from saturation
+    ///
+    /// Reads one measurement per output data period until one axis hits the
+    /// full-scale limit like in `clipping_stats`, e.g. during an impact, and
+    /// then until the bias
+    /// corrected rate of every axis is within three standard deviations of
+    /// the noise of `gyro_measurement`. Returns the time in microseconds from
+    /// the last saturated measurement to the first one within the noise
+    /// floor. Returns `Error::Timeout` if the sensor does not saturate within
+    /// `max_periods` output data periods, or does not recover within
+    /// `max_periods` periods once it saturated.
+    #[bisync]
+    pub async fn measure_recovery_time(
+        &mut self,
+        max_periods: u32,
+        delay: &mut impl DelayNs,
+    ) -> Result<u32, Error<Spi::Error>> {
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        let scale = self.scale;
+        let odr = Odr::from_u8(reg1);
+        let period_us = odr.period_us();
+        // Compare squares to avoid the square root
+        let threshold = 9.0 * Measurement::variance(odr, Bandwidth::from_u8(reg1), scale);
+
+        let mut saturated = false;
+        for _ in 0..max_periods {
+            delay.delay_us(period_us).await;
+            if self.gyro_uncorrected().await?.saturated().contains(&true) {
+                saturated = true;
+                break;
+            }
+        }
+        if !saturated {
+            return Err(Error::Timeout);
+        }
+
+        let mut periods = 0u32;
+        for _ in 0..max_periods {
+            delay.delay_us(period_us).await;
+            let raw = self.gyro_uncorrected().await?;
+            if raw.saturated().contains(&true) {
+                periods = 0;
+                continue;
+            }
+            periods += 1;
+
+            let rate = scale.degrees_f32x3(self.correct(raw));
+            if [rate.x, rate.y, rate.z].iter().all(|r| r * r <= threshold) {
+                return Ok(periods * period_us);
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Get the per-axis scale factor corrections, in X, Y, Z order
+    pub fn scale_correction(&self) -> [f32; 3] {
+        self.scale_correction
+    }
+
+    /// Set the per-axis scale factor corrections, in X, Y, Z order
+    ///
+    /// The corrections multiply the rates in dps returned by `gyro_dps`,
+    /// `gyro_measurement`, `gyro_integrate_into`, `gyro_for_complementary`,
+    /// `gyro_thermal_tracked` and the angle integration. They are applied per
+    /// axis after the bias and the sign convention, before the mount matrix,
+    /// and are 1.0 by default. A correction is a gain and is not affected by
+    /// the sign convention.
+    pub fn set_scale_correction(&mut self, correction: [f32; 3]) -> &mut Self {
+        self.scale_correction = correction;
+        self
+    }
+
+    /// Get the mount matrix
+    pub fn mount_matrix(&self) -> [[f32; 3]; 3] {
+        self.mount
+    }
+
+    /// Set the mount matrix
+    ///
+    /// The matrix rotates rates in dps from the sensor frame into the body
+    /// frame, i.e. `body = m * sensor`, for sensors that are not mounted
+    /// axis-aligned. It is applied after the sign convention and the scale
+    /// factor correction to the same rates as the scale factor correction.
+    /// The identity matrix is the default.
+    pub fn set_mount_matrix(&mut self, m: [[f32; 3]; 3]) -> &mut Self {
+        self.mount = m;
+        self
+    }
+
+    /// Calibrate the scale factor of `axis` on a turntable
+    ///
+    /// The turntable must turn at `rate_pos_dps` about `axis` when this is
+    /// called. After averaging `SCALE_VERIFICATION_SAMPLES` measurements, it
+    /// waits for the turntable to be reversed to `rate_neg_dps`, i.e. for a
+    /// measurement closer to `rate_neg_dps` than to `rate_pos_dps`, then
+    /// waits `SETTLING_PERIODS` output periods and averages again. The
+    /// correction is the ratio of the commanded span to the measured span, so
+    /// the bias cancels out and a sensor with an asymmetric response is
+    /// corrected over the whole span.
+    ///
+    /// The rates are about the axis of the sensor, the sign convention is not
+    /// applied to the measurements. The correction is stored for `axis` and
+    /// returned. If the measured span is zero the stored correction is left
+    /// unchanged. Returns `Error::Timeout` if the turntable is not reversed
+    /// within `TURNTABLE_REVERSAL_TIMEOUT_US`.
+    #[bisync]
+    pub async fn calibrate_scale_two_point(
+        &mut self,
+        axis: Axis,
+        rate_pos_dps: f32,
+        rate_neg_dps: f32,
+        delay: &mut impl DelayNs,
+    ) -> Result<f32, Error<Spi::Error>> {
+        let period_us = self.odr().await?.period_us();
+        let midpoint = (rate_pos_dps + rate_neg_dps) / 2.0;
+
+        let pos = self.turntable_mean(axis, delay, period_us).await?;
+        let mut reversed = false;
+        for _ in 0..=TURNTABLE_REVERSAL_TIMEOUT_US / period_us {
+            delay.delay_us(period_us).await;
+            let raw = self.gyro_uncorrected().await?;
+            let rate = self.scale.degrees(self.bias.apply(raw).axis(axis));
+            if (rate - midpoint) * (rate_neg_dps - midpoint) > 0.0 {
+                reversed = true;
+                break;
+            }
+        }
+        if !reversed {
+            return Err(Error::Timeout);
+        }
+        delay.delay_us(SETTLING_PERIODS * period_us).await;
+        let neg = self.turntable_mean(axis, delay, period_us).await?;
+
+        let correction = (rate_pos_dps - rate_neg_dps) / (pos - neg);
+        if correction.is_finite() {
+            self.scale_correction[axis.index()] = correction;
+        }
+        Ok(correction)
+    }
+
+    /// Mean rate of `axis` in dps over `SCALE_VERIFICATION_SAMPLES`
+    /// measurements without the sign convention, for
+    /// `calibrate_scale_two_point`
+    #[bisync]
+    async fn turntable_mean(
+        &mut self,
+        axis: Axis,
+        delay: &mut impl DelayNs,
+        period_us: u32,
+    ) -> Result<f32, Spi::Error> {
+        let mut sum = 0i32;
+        for _ in 0..SCALE_VERIFICATION_SAMPLES {
+            delay.delay_us(period_us).await;
+            let raw = self.gyro_uncorrected().await?;
+            sum += self.bias.apply(raw).axis(axis) as i32;
+        }
+        Ok(sum as f32 / SCALE_VERIFICATION_SAMPLES as f32 * self.scale.dps_per_lsb())
+    }
+
+    /// Gyroscope measurements plus the variance over a window of measurements
+    ///
+    /// Pushes the new measurement into the caller-owned `window` and returns
+    /// it together with the per-axis variance (in raw LSB²) of the window.
+    /// A high variance while the sensor is at rest indicates a problem.
+    #[bisync]
+    pub async fn gyro_with_variance<const N: usize>(
+        &mut self,
+        window: &mut VarWindow<N>,
+    ) -> Result<(I16x3, F32x3), Spi::Error> {
+        let gyro = self.gyro().await?;
+        window.push(gyro);
+        Ok((gyro, window.variance()))
+    }
+
+    /// Per-axis signal-to-noise ratio in dB
+    ///
+    /// Pushes a new measurement into `noise_window` like `gyro_with_variance`
+    /// and compares the power of `signal`, an estimate of the rate in degrees
+    /// per second, to the variance of the window converted with the current
+    /// `Scale`.
+    #[cfg(feature = "libm")]
+    #[bisync]
+    pub async fn snr_db<const N: usize>(
+        &mut self,
+        signal: F32x3,
+        noise_window: &mut VarWindow<N>,
+    ) -> Result<F32x3, Spi::Error> {
+        let dps_per_lsb = self.scale.dps_per_lsb();
+        let (_, var) = self.gyro_with_variance(noise_window).await?;
+        let noise = |var: f32| var * dps_per_lsb * dps_per_lsb;
+        let snr = |signal: f32, var: f32| 10.0 * libm::log10f(signal * signal / noise(var));
+        Ok(F32x3 {
+            x: snr(signal.x, var.x),
+            y: snr(signal.y, var.y),
+            z: snr(signal.z, var.z),
+        })
+    }
+
+    /// Measure the time from power-up until the first sample is available
+    ///
+    /// Powers the sensor down and reads the output registers, which discards
+    /// a pending sample and clears the data-ready signal. Then powers it up
+    /// again with the axes selected with `set_axes_enabled`, all by default,
+    /// and polls the `drdy` pin until it goes high. Returns the elapsed time
+    /// in microseconds as measured by `clock`, or `Error::Timeout` if the pin
+    /// stays low for longer than `STARTUP_TIMEOUT_US`.
+    ///
+    /// The data-ready signal must already be routed to the DRDY/INT2 pin
+    /// (`I2_DRDY` in `CTRL_REG3`) with the default active-high polarity.
+    #[bisync]
+    pub async fn measure_startup_time(
+        &mut self,
+        drdy: &mut impl InputPin,
+        clock: &mut impl SampleClock,
+    ) -> Result<u32, Error<Spi::Error>> {
+        let reg1 =
+            self.read_register(Register::CTRL_REG1).await? & !(CTRL_REG1_PD | CTRL_REG1_AXES);
+        self.write_register(Register::CTRL_REG1, reg1).await?;
+        // A sample left unread would keep DRDY high from the start
+        self.gyro_uncorrected().await?;
+
+        let start = clock.now_us();
+        self.write_register(
+            Register::CTRL_REG1,
+            reg1 | PowerMode::Normal.to_u8(self.axes),
+        )
+        .await?;
+        while !drdy.is_high().map_err(|_| Error::Pin)? {
+            if clock.now_us().wrapping_sub(start) > STARTUP_TIMEOUT_US {
+                return Err(Error::Timeout);
+            }
+        }
+
+        Ok(clock.now_us().wrapping_sub(start))
+    }
+
+    /// Read and decode all configuration, data and interrupt registers
+    ///
+    /// The registers are read in three bursts, the control registers, the
+    /// temperature, status and output registers and the FIFO and interrupt
+    /// registers. With the FIFO enabled, a burst across `OUT_Z_H` would wrap
+    /// around to `OUT_X_L` instead of continuing with `FIFO_CTRL_REG`.
+    /// `REFERENCE` is read separately and skipped in
+    /// `HighPassMode::NormalReset`, where reading it resets the high-pass
+    /// filter.
+    ///
+    /// # Note
+    /// This is not a passive snapshot, some of the registers change the
+    /// state of the sensor when read:
+    ///
+    /// - `INT1_SRC`, which clears a latched interrupt
+    /// - the output registers, which clears the new data flags of
+    ///   `STATUS_REG` and, with the FIFO enabled, consumes the oldest sample
+    ///
+    /// Avoid calling this while the application relies on latched interrupts,
+    /// the data-ready signal or the FIFO contents.
+    #[bisync]
+    pub async fn read_all_registers(&mut self) -> Result<Registers, Spi::Error> {
+        let mut image = [0u8; Registers::LEN];
+        // The first byte of every burst is clocked out with the address
+        let mut ctrl = [0u8; 6];
+        self.read_many(Register::CTRL_REG1, &mut ctrl).await?;
+        image[..Registers::REFERENCE].copy_from_slice(&ctrl[1..]);
+        if DeviceState::saves_reference(image[1]) {
+            image[Registers::REFERENCE] = self.read_register(Register::REFERENCE).await?;
+        }
+        let mut data = [0u8; 9];
+        self.read_many(Register::OUT_TEMP, &mut data).await?;
+        image[Registers::OUT_TEMP..Registers::FIFO_CTRL].copy_from_slice(&data[1..]);
+        let mut int1 = [0u8; 12];
+        self.read_many(Register::FIFO_CTRL_REG, &mut int1).await?;
+        image[Registers::FIFO_CTRL..].copy_from_slice(&int1[1..]);
+
+        // STATUS_REG is at offset 7 from CTRL_REG1
+        self.decode_status(image[7]);
+        Ok(Registers::decode(&image))
+    }
+
+    /// Get the current power mode
+    #[bisync]
+    pub async fn power_mode(&mut self) -> Result<PowerMode, Spi::Error> {
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        Ok(PowerMode::from_u8(reg1))
+    }
+
+    /// Set the power mode
+    ///
+    /// Only the `PD` and axis enable bits of `CTRL_REG1` are changed.
+    #[bisync]
+    pub async fn set_power_mode(&mut self, mode: PowerMode) -> Result<&mut Self, Spi::Error> {
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        let reg1 = (reg1 & !(CTRL_REG1_PD | CTRL_REG1_AXES)) | mode.to_u8(self.axes);
+        self.write_register(Register::CTRL_REG1, reg1).await?;
+        Ok(self)
+    }
+
+    /// Power the sensor down
+    ///
+    /// Clears only the `PD` bit of `CTRL_REG1`. The output data rate,
+    /// bandwidth and axis enable bits are preserved.
+    #[bisync]
+    pub async fn power_down(&mut self) -> Result<&mut Self, Spi::Error> {
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        self.write_register(Register::CTRL_REG1, reg1 & !CTRL_REG1_PD)
+            .await?;
+        Ok(self)
+    }
+
+    /// Power the sensor up from power-down or sleep mode
+    ///
+    /// Sets the `PD` bit of `CTRL_REG1`, preserving the output data rate,
+    /// bandwidth and axis enable bits. If all axes are disabled, e.g. when
+    /// leaving sleep mode, the axes selected with `set_axes_enabled` are
+    /// enabled, all by default.
+    #[bisync]
+    pub async fn power_up(&mut self) -> Result<&mut Self, Spi::Error> {
+        let mut reg1 = self.read_register(Register::CTRL_REG1).await? | CTRL_REG1_PD;
+        if reg1 & CTRL_REG1_AXES == 0 {
+            reg1 |= self.axes;
+        }
+        self.write_register(Register::CTRL_REG1, reg1).await?;
+        Ok(self)
+    }
+
+    /// Put the sensor to sleep
+    ///
+    /// Keeps the `PD` bit of `CTRL_REG1` set but disables all axes, which
+    /// reduces the supply current while allowing a faster wake-up with
+    /// `power_up` than from power-down mode.
+    #[bisync]
+    pub async fn sleep(&mut self) -> Result<&mut Self, Spi::Error> {
+        self.set_power_mode(PowerMode::Sleep).await
+    }
+
+    /// Activity based switching between normal and sleep mode
+    ///
+    /// Meant to be called periodically. In normal mode a measurement is read
+    /// and, once `idle_samples` consecutive measurements had a rate below
+    /// `activity_threshold_dps` on all axes, the sensor is put to sleep.
+    ///
+    /// No measurements are produced while sleeping, so a call in sleep mode
+    /// wakes the sensor up and the next call checks for motion: one
+    /// measurement above the threshold keeps it awake, otherwise it goes back
+    /// to sleep. A powered down sensor is left untouched.
+    ///
+    /// Returns the power mode after the call.
+    #[bisync]
+    pub async fn auto_power(
+        &mut self,
+        activity_threshold_dps: f32,
+        idle_samples: u16,
+    ) -> Result<PowerMode, Spi::Error> {
+        match self.power_mode().await? {
+            PowerMode::Normal => {
+                let threshold = self.scale.to_raw(activity_threshold_dps).unsigned_abs();
+                if self.gyro().await?.abs_max() >= threshold {
+                    self.idle_count = 0;
+                    return Ok(PowerMode::Normal);
+                }
+                self.idle_count = self.idle_count.saturating_add(1);
+                if self.idle_count < idle_samples {
+                    return Ok(PowerMode::Normal);
+                }
+                self.set_power_mode(PowerMode::Sleep).await?;
+                Ok(PowerMode::Sleep)
+            }
+            PowerMode::Sleep => {
+                self.idle_count = idle_samples.saturating_sub(1);
+                self.set_power_mode(PowerMode::Normal).await?;
+                Ok(PowerMode::Normal)
+            }
+            PowerMode::PowerDown => Ok(PowerMode::PowerDown),
+        }
+    }
+
+    /// Integrate the Z-axis rate into a heading
+    ///
+    /// For applications where only the Z-axis rotates (turntables, pan
+    /// heads). The angle rotated during `dt_s` seconds is added to `state`,
+    /// which is kept wrapped to `[0, 360)` degrees. Returns the angle rotated
+    /// in degrees.
+    ///
+    /// The rate is read with `gyro_dps`, so the Z-axis is that of the body
+    /// frame and the scale factor correction applies, like for
+    /// `gyro_integrate`.
+    #[bisync]
+    pub async fn heading_delta_deg(
+        &mut self,
+        dt_s: f32,
+        state: &mut f32,
+    ) -> Result<f32, Spi::Error> {
+        let delta = self.gyro_dps().await?[2] * dt_s;
+
+        // Euclidean remainder, `f32::rem_euclid` is not available in `core`
+        let heading = (*state + delta) % 360.0;
+        let heading = if heading < 0.0 {
+            heading + 360.0
+        } else {
+            heading
+        };
+        // A tiny negative heading rounds up to 360
+        *state = if heading < 360.0 { heading } else { 0.0 };
+        Ok(delta)
+    }
+
+    /// Axes enabled with `set_axes_enabled`, as `(x, y, z)`
+    ///
+    /// All axes are enabled by default. The axes stay selected while the
+    /// sensor is powered down or asleep.
+    pub fn axes_enabled(&self) -> (bool, bool, bool) {
+        let enabled = |axis: Axis| self.axes & axis.enable_bit() != 0;
+        (enabled(Axis::X), enabled(Axis::Y), enabled(Axis::Z))
+    }
+
+    /// Enable or disable the individual axes
+    ///
+    /// The enabled axes are also restored when switching back to
+    /// `PowerMode::Normal`. Only has an immediate effect in normal mode.
+    ///
+    /// Disabling all axes puts the sensor to sleep. The previously enabled
+    /// axes stay selected and are restored by `power_up`.
+    #[bisync]
+    pub async fn set_axes_enabled(
+        &mut self,
+        x: bool,
+        y: bool,
+        z: bool,
+    ) -> Result<&mut Self, Spi::Error> {
+        let mut axes = 0;
+        for (axis, enabled) in [(Axis::X, x), (Axis::Y, y), (Axis::Z, z)] {
+            if enabled {
+                axes |= axis.enable_bit();
+            }
+        }
+        // Keep the axes to wake up with if the sensor is put to sleep
+        if axes != 0 {
+            self.axes = axes;
+        }
+        let reg1 = self.read_register(Register::CTRL_REG1).await?;
+        if PowerMode::from_u8(reg1) == PowerMode::Normal {
+            self.write_register(Register::CTRL_REG1, (reg1 & !CTRL_REG1_AXES) | axes)
+                .await?;
+        }
+        Ok(self)
+    }
+
+    /// Whether status masking is enabled
+    pub fn status_masking(&self) -> bool {
+        self.status_masking
+    }
+
+    /// Enable or disable status masking
+    ///
+    /// When enabled, `status` clears the new data and overrun bits of axes
+    /// disabled with `set_axes_enabled` and derives `new_data` and `overrun`
+    /// from the enabled axes only. Disabled by default.
+    pub fn set_status_masking(&mut self, enabled: bool) -> &mut Self {
+        self.status_masking = enabled;
+        self
+    }
+
+    /// Temperature, status and gyroscope measurements as a C-compatible struct
+    #[bisync]
+    pub async fn all_c(&mut self) -> Result<GyroSampleC, Spi::Error> {
+        let mut bytes = [0u8; 9];
+        self.read_many(Register::OUT_TEMP, &mut bytes).await?;
+
+        self.decode_status(bytes[2]);
+        let gyro = self.decode(&bytes[3..]);
+        Ok(GyroSampleC {
+            x: gyro.x,
+            y: gyro.y,
+            z: gyro.z,
+            temp: bytes[1] as i8,
+            status: bytes[2],
+        })
+    }
+
+    /// Typical supply current in µA for the detected model and power mode
+    ///
+    /// The output data rate does not change the estimate, see
+    /// `PowerMode::typical_current_ua`.
+    #[bisync]
+    pub async fn estimated_current_ua(&mut self) -> Result<u32, Error<Spi::Error>> {
+        let model = self.model().await?;
+        Ok(self.power_mode().await?.typical_current_ua(model))
+    }
+
+    /// Gyroscope measurements of a subset of the axes
+    ///
+    /// `MASK` is a combination of `AXES_X`, `AXES_Y` and `AXES_Z`. Only the
+    /// registers from the first to the last selected axis are read, so e.g.
+    /// `AXES_X | AXES_Y` needs a 4 byte instead of a 6 byte burst. The
+    /// returned array is indexed X, Y, Z and unselected axes are zero. The
+    /// selected axes are corrected like in `gyro`, i.e. the bias is
+    /// subtracted and the sign convention applied.
+    #[bisync]
+    pub async fn gyro_axes<const MASK: u8>(&mut self) -> Result<[i16; 3], Spi::Error> {
+        const { assert!(MASK & !(AXES_X | AXES_Y | AXES_Z) == 0) };
+        let mut out = [0i16; 3];
+        if MASK == 0 {
+            return Ok(out);
+        }
+        let first = MASK.trailing_zeros() as usize;
+        let last = 7 - MASK.leading_zeros() as usize;
+
+        let mut bytes = [0u8; 7];
+        let len = (last - first + 1) * 2;
+        match self.read_mode {
+            ReadMode::Burst => {
+                self.read_many(GYRO_OUT_REGISTERS[first * 2], &mut bytes[..len + 1])
+                    .await?
+            }
+            ReadMode::SingleByte => {
+                for i in (first..=last).filter(|i| MASK & (1 << i) != 0) {
+                    let offset = (i - first) * 2;
+                    bytes[offset + 1] = self.read_register(GYRO_OUT_REGISTERS[i * 2]).await?;
+                    bytes[offset + 2] = self.read_register(GYRO_OUT_REGISTERS[i * 2 + 1]).await?;
+                }
+            }
+        }
+
+        for (i, val) in out.iter_mut().enumerate() {
+            if MASK & (1 << i) != 0 {
+                let offset = (i - first) * 2;
+                let pair = [bytes[offset + 1], bytes[offset + 2]];
+                *val = if self.big_endian {
+                    i16::from_be_bytes(pair)
+                } else {
+                    i16::from_le_bytes(pair)
+                };
+            }
+        }
+        let gyro = self.correct(I16x3 {
+            x: out[0],
+            y: out[1],
+            z: out[2],
+        });
+        let selected = |i: usize, val: i16| if MASK & (1 << i) != 0 { val } else { 0 };
+        Ok([
+            selected(0, gyro.x),
+            selected(1, gyro.y),
+            selected(2, gyro.z),
+        ])
+    }
+
+    /// Count clipped measurements per axis
+    ///
+    /// Reads `samples` measurements, one per output data period, and returns
+    /// how many of them hit the full-scale limit on the X, Y and Z axis. The
+    /// raw measurements are checked, before bias and sign correction.
+    #[bisync]
+    pub async fn clipping_stats(
+        &mut self,
+        samples: u16,
+        delay: &mut impl DelayNs,
+    ) -> Result<[u16; 3], Spi::Error> {
+        let period_us = self.odr().await?.period_us();
+
+        let mut clipped = [0u16; 3];
+        for _ in 0..samples {
+            delay.delay_us(period_us).await;
+            let gyro = self.gyro_uncorrected().await?;
+            for (count, saturated) in clipped.iter_mut().zip(gyro.saturated()) {
+                *count += saturated as u16;
+            }
+        }
+        Ok(clipped)
+    }
+
+    /// Verify the INT1 pin wiring by forcing an interrupt
+    ///
+    /// Temporarily enables high and low events on all axes with a threshold
+    /// of 0 and routes interrupt 1 to the INT1 pin, then checks whether
+    /// `int_pin` asserts within the next two samples, taking the configured
+    /// `H_Lactive` polarity into account. Returns `Error::Timeout` if no two
+    /// samples arrive within `INTERRUPT_TEST_POLLS` polls of `STATUS_REG`.
+    /// The previous `CTRL_REG3` and interrupt configuration is restored
+    /// afterwards, also if an error occurred.
+    ///
+    /// The sensor must be in normal mode.
+    #[bisync]
+    pub async fn force_interrupt_test(
+        &mut self,
+        int_pin: &mut impl InputPin,
+    ) -> Result<bool, Error<Spi::Error>> {
+        let reg3 = self.read_register(Register::CTRL_REG3).await?;
+        let mut saved = [0u8; INT1_CONFIG_REGISTERS.len()];
+        for (val, reg) in saved.iter_mut().zip(INT1_CONFIG_REGISTERS) {
+            *val = self.read_register(reg).await?;
+        }
+
+        let asserted = self.force_interrupt(int_pin, reg3).await;
+
+        self.write_register(Register::CTRL_REG3, reg3).await?;
+        for (val, reg) in saved.into_iter().zip(INT1_CONFIG_REGISTERS) {
+            self.write_register(reg, val).await?;
+        }
+        // Clear the interrupt in case it is latched
+        self.read_register(Register::INT1_SRC).await?;
+
+        asserted
+    }
+
+    /// Configure the forced interrupt of `force_interrupt_test` and wait up
+    /// to two samples for `int_pin` to assert
+    #[bisync]
+    async fn force_interrupt(
+        &mut self,
+        int_pin: &mut impl InputPin,
+        reg3: u8,
+    ) -> Result<bool, Error<Spi::Error>> {
+        for reg in INT1_CONFIG_REGISTERS {
+            self.write_register(reg, 0).await?;
+        }
+        self.write_register(Register::INT1_CFG, INT1_CFG_ALL_EVENTS)
+            .await?;
+        self.write_register(Register::CTRL_REG3, reg3 | CTRL_REG3_I1_INT1)
+            .await?;
+
+        let active_low = reg3 & CTRL_REG3_H_LACTIVE != 0;
+        let mut samples = 0;
+        for _ in 0..INTERRUPT_TEST_POLLS {
+            if int_pin.is_high().map_err(|_| Error::Pin)? != active_low {
+                return Ok(true);
+            }
+            let sts = Status::from_u8(self.read_register(Register::STATUS_REG).await?);
+            if sts.new_data {
+                self.gyro().await?;
+                samples += 1;
+                if samples == 2 {
+                    return Ok(false);
+                }
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Read the angular rate and feed it into an `Integrator`
+    ///
+    /// The rate is converted to degrees per second using the current `Scale`
+    /// and accumulated over `dt` seconds.
+    #[bisync]
+    pub async fn gyro_integrate_into(
+        &mut self,
+        integrator: &mut impl Integrator,
+        dt: f32,
+    ) -> Result<(), Spi::Error> {
+        let scale = self.scale;
+        let gyro = self.gyro().await?;
+        let rate = self.rate_dps(scale, gyro);
+        integrator.accumulate(rate, dt);
+        Ok(())
+    }
+
+    /// Gyroscope measurements plus the rate of change of the angular rate
+    ///
+    /// Returns the new measurement and the per-axis derivative in degrees
+    /// per second² relative to `prev`, which was read `dt_s` seconds earlier.
+    /// `prev` is updated with the new measurement.
+    #[bisync]
+    pub async fn gyro_with_jerk(
+        &mut self,
+        prev: &mut I16x3,
+        dt_s: f32,
+    ) -> Result<(I16x3, F32x3), Spi::Error> {
+        let scale = self.scale;
+        let gyro = self.gyro().await?;
+        let jerk = F32x3 {
+            x: scale.dps_per_lsb() * (gyro.x as f32 - prev.x as f32) / dt_s,
+            y: scale.dps_per_lsb() * (gyro.y as f32 - prev.y as f32) / dt_s,
+            z: scale.dps_per_lsb() * (gyro.z as f32 - prev.z as f32) / dt_s,
+        };
+        *prev = gyro;
+        Ok((gyro, jerk))
+    }
+
+    /// Apply a configuration setting given as key-value strings
+    ///
+    /// Supported keys and values:
+    ///
+    /// - `"odr"`: `"95"`, `"190"`, `"380"` or `"760"` (Hz)
+    /// - `"scale"`: `"250"`, `"500"` or `"2000"` (degrees per second)
+    /// - `"bandwidth"`: `"low"`, `"medium"`, `"high"` or `"maximum"`
+    /// - `"bdu"`: `"true"`/`"1"` or `"false"`/`"0"` (block data update)
+    ///
+    /// Returns `Error::UnknownSetting` for other keys and `Error::BadValue` for
+    /// other values.
+    #[bisync]
+    pub async fn apply_setting(&mut self, key: &str, value: &str) -> Result<(), Error<Spi::Error>> {
+        match key {
+            "odr" => {
+                let odr = Odr::from_setting(value).ok_or(Error::BadValue)?;
+                self.set_odr(odr).await?;
+            }
+            "scale" => {
+                let scale = Scale::from_setting(value).ok_or(Error::BadValue)?;
+                self.set_scale(scale).await?;
+            }
+            "bandwidth" => {
+                let bw = Bandwidth::from_setting(value).ok_or(Error::BadValue)?;
+                self.set_bandwidth(bw).await?;
+            }
+            "bdu" => {
+                let bdu = match value {
+                    "true" | "1" => true,
+                    "false" | "0" => false,
+                    _ => return Err(Error::BadValue),
+                };
+                self.set_block_data_update(bdu).await?;
+            }
+            _ => return Err(Error::UnknownSetting),
+        }
+        Ok(())
+    }
+
+    /// Gyroscope measurements with a deadband applied
+    ///
+    /// Every axis whose magnitude is below `threshold` (raw LSB) is set to 0
+    /// to suppress the small non-zero rates reported at rest.
+    #[bisync]
+    pub async fn gyro_deadband(&mut self, threshold: i16) -> Result<I16x3, Spi::Error> {
+        let deadband = |val: i16| {
+            if val.unsigned_abs() < threshold.unsigned_abs() {
+                0
+            } else {
+                val
+            }
+        };
+        let gyro = self.gyro().await?;
+        Ok(I16x3 {
+            x: deadband(gyro.x),
+            y: deadband(gyro.y),
+            z: deadband(gyro.z),
+        })
+    }
+
+    /// Save all writable configuration registers
+    ///
+    /// Reading `REFERENCE` resets the high-pass filter in
+    /// `HighPassMode::NormalReset`, so in that mode `REFERENCE` is neither
+    /// saved nor restored. It only affects the output in
+    /// `HighPassMode::Reference`.
+    #[bisync]
+    pub async fn save_state(&mut self) -> Result<DeviceState, Spi::Error> {
+        let mut registers = [0u8; STATE_REGISTERS.len()];
+        for (i, reg) in STATE_REGISTERS.into_iter().enumerate() {
+            // CTRL_REG2 is saved first
+            if let Register::REFERENCE = reg {
+                if !DeviceState::saves_reference(registers[0]) {
+                    continue;
+                }
+            }
+            registers[i] = self.read_register(reg).await?;
+        }
+        Ok(DeviceState {
+            ctrl_reg1: self.read_register(Register::CTRL_REG1).await?,
+            registers,
+            axes: self.axes,
+        })
+    }
+
+    /// Restore configuration registers saved with `save_state`
+    #[bisync]
+    pub async fn restore_state(&mut self, state: &DeviceState) -> Result<(), Spi::Error> {
+        for (val, reg) in state.registers.into_iter().zip(STATE_REGISTERS) {
+            if let Register::REFERENCE = reg {
+                if !DeviceState::saves_reference(state.registers[0]) {
+                    continue;
+                }
+            }
+            self.write_register(reg, val).await?;
+        }
+        self.write_register(Register::CTRL_REG1, state.ctrl_reg1)
+            .await?;
+        self.axes = state.axes;
+        Ok(())
+    }
+
+    /// Run `f` and restore the configuration afterwards
+    ///
+    /// All writable configuration registers are saved before calling `f`, which
+    /// may reconfigure the sensor freely, and are restored afterwards, also if
+    /// `f` returns an error.
+    ///
+    /// Only available on the blocking driver. With the asynchronous driver, call
+    /// `save_state` and `restore_state` around the reconfiguration instead.
+    #[only_sync]
+    pub fn with_saved_state<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, Spi::Error>,
+    ) -> Result<T, Spi::Error> {
+        let state = self.save_state()?;
+        let result = f(self);
+        self.restore_state(&state)?;
+        result
+    }
+
+    /// Gyroscope measurements converted to a fixed resolution
+    ///
+    /// Converts the measurement to `lsb_per_dps` LSB per degree per second,
+    /// independent of the configured `Scale`. Useful for fixed-point code
+    /// which expects a specific resolution.
+    #[bisync]
+    pub async fn gyro_scaled_to(
+        &mut self,
+        lsb_per_dps: u16,
+    ) -> Result<(i32, i32, i32), Spi::Error> {
+        let scale = self.scale;
+        let gyro = self.gyro().await?;
+        Ok((
+            scale.rescale(gyro.x, lsb_per_dps),
+            scale.rescale(gyro.y, lsb_per_dps),
+            scale.rescale(gyro.z, lsb_per_dps),
+        ))
+    }
+
+    /// Read and decode `FIFO_SRC_REG`
+    #[bisync]
+    pub async fn fifo_status(&mut self) -> Result<FifoStatus, Spi::Error> {
+        let src = self.read_register(Register::FIFO_SRC_REG).await?;
+        Ok(FifoStatus::from_u8(src))
+    }
+
+    /// Whether the FIFO is empty
+    ///
+    /// Reads the `EMPTY` flag of `FIFO_SRC_REG`. Use this to terminate a drain
+    /// loop instead of relying on the stored sample count alone.
+    #[bisync]
+    pub async fn fifo_is_empty(&mut self) -> Result<bool, Spi::Error> {
+        Ok(self.fifo_status().await?.empty)
+    }
+
+    /// Get the FIFO watermark level
+    #[bisync]
+    pub async fn fifo_watermark(&mut self) -> Result<u8, Spi::Error> {
+        Ok(self.read_register(Register::FIFO_CTRL_REG).await? & FIFO_CTRL_WTM)
+    }
+
+    /// Set the FIFO watermark level
+    ///
+    /// `FifoStatus::watermark` is set and, if enabled, the watermark
+    /// interrupt is raised once `level` samples are stored. `level` saturates
+    /// at 31. The FIFO mode bits of `FIFO_CTRL_REG` are preserved.
+    #[bisync]
+    pub async fn set_fifo_watermark(&mut self, level: u8) -> Result<&mut Self, Spi::Error> {
+        self.change_config(
+            Register::FIFO_CTRL_REG,
+            FifoWatermark(level.min(FIFO_CTRL_WTM)),
+        )
+        .await
+    }
+
+    /// Set the FIFO watermark level as a fraction of the FIFO depth
+    ///
+    /// `fraction` is clamped to `0.0..=1.0` and mapped to the nearest
+    /// watermark level in `0..=31`, e.g. `0.75` results in 23. The FIFO mode
+    /// bits of `FIFO_CTRL_REG` are preserved.
+    #[bisync]
+    pub async fn set_fifo_watermark_fraction(
+        &mut self,
+        fraction: f32,
+    ) -> Result<&mut Self, Spi::Error> {
+        let level = (fraction.clamp(0.0, 1.0) * FIFO_CTRL_WTM as f32 + 0.5) as u8;
+        self.set_fifo_watermark(level).await
+    }
+
+    /// Get the sign convention, i.e. which of the X, Y and Z axes are inverted
+    pub fn sign_convention(&self) -> [bool; 3] {
+        self.invert
+    }
+
+    /// Set the sign convention, i.e. which of the X, Y and Z axes are inverted
+    ///
+    /// Inverted axes are negated by `gyro` and `all` to match the body frame
+    /// of the application. No axis is inverted by default.
+    pub fn set_sign_convention(&mut self, invert: [bool; 3]) -> &mut Self {
+        self.invert = invert;
+        self
+    }
+
+    /// Measure the duration of a `gyro` read in microseconds
+    #[bisync]
+    pub async fn measure_read_latency(
+        &mut self,
+        clock: &mut impl SampleClock,
+    ) -> Result<u32, Spi::Error> {
+        let start = clock.now_us();
+        self.gyro().await?;
+        Ok(clock.now_us().wrapping_sub(start))
+    }
+
+    /// Read the samples stored in the FIFO into `out`
+    ///
+    /// Reads `min(stored, out.len())` samples, oldest first, in one burst and
+    /// returns the number of samples written to `out`. The samples are bias
+    /// and sign corrected like `gyro`. Samples which do not fit into `out`
+    /// stay in the FIFO.
+    #[bisync]
+    pub async fn read_fifo(&mut self, out: &mut [I16x3]) -> Result<usize, Spi::Error> {
+        let stored = self.fifo_status().await?.stored as usize;
+        let count = stored.min(out.len());
+        if count > 0 {
+            self.read_fifo_samples(&mut out[..count]).await?;
+        }
+        Ok(count)
+    }
+
+    /// Drain at most `max` samples from the FIFO
+    ///
+    /// Reads `min(stored, max, out.len())` samples into `out` and returns the
+    /// number of samples read together with whether more samples remain in
+    /// the FIFO, so a consumer can apply backpressure.
+    #[bisync]
+    pub async fn drain_with_limit(
+        &mut self,
+        out: &mut [I16x3],
+        max: usize,
+    ) -> Result<(usize, bool), Spi::Error> {
+        let stored = self.fifo_status().await?.stored as usize;
+        let count = stored.min(max).min(out.len());
+        if count > 0 {
+            self.read_fifo_samples(&mut out[..count]).await?;
+        }
+        Ok((count, stored > count))
+    }
+
+    /// Detect the sensor model from the `WHO_AM_I` register
+    ///
+    /// Returns `Error::InvalidWhoAmI` if the value matches no known model.
+    #[bisync]
+    pub async fn model(&mut self) -> Result<Model, Error<Spi::Error>> {
+        let who_am_i = self.who_am_i().await?;
+        Model::from_who_am_i(who_am_i).ok_or(Error::InvalidWhoAmI(who_am_i))
+    }
+
+    /// Capabilities of the detected sensor model
+    #[bisync]
+    pub async fn capabilities(&mut self) -> Result<Capabilities, Error<Spi::Error>> {
+        Ok(self.model().await?.capabilities())
+    }
+
+    /// Status from the most recent read which included `STATUS_REG`
+    ///
+    /// Updated by `status`, `all`, `all_detailed`, `all_c` and
+    /// `read_all_registers`, so no extra bus transfer is needed if one of
+    /// those is already part of the application's read loop.
+    pub fn cached_status(&self) -> Status {
+        self.last_status
+    }
+
+    /// Configure the sensor for precise static measurements
+    ///
+    /// Selects the lowest output data rate, the lowest bandwidth, the most
+    /// sensitive scale (`Scale::Dps250`) and enables block data update. The
+    /// lowest output data rate is `LowOdr::Hz12_5` on the L3GD20H, see
+    /// `set_low_odr` for its caveats, and `Odr::Hz95` on the L3GD20.
+    #[bisync]
+    pub async fn configure_precision(&mut self) -> Result<(), Error<Spi::Error>> {
+        if self.capabilities().await?.low_odr {
+            self.set_low_odr(LowOdr::Hz12_5).await?;
+        } else {
+            self.set_odr(Odr::Hz95).await?;
+        }
+        self.set_bandwidth(Bandwidth::Low).await?;
+        self.set_scale(Scale::Dps250).await?;
+        self.set_block_data_update(true).await?;
+        Ok(())
+    }
+
+    /// Configure the sensor for fast motion
+    ///
+    /// Selects the highest output data rate (`Odr::Hz760`), the highest
+    /// bandwidth, the least sensitive scale (`Scale::Dps2000`), enables block
+    /// data update and enables the FIFO in Stream mode. The FIFO watermark
+    /// level is preserved. A low output data rate of the L3GD20H is cleared.
+    #[bisync]
+    pub async fn configure_high_rate(&mut self) -> Result<(), Error<Spi::Error>> {
+        self.clear_low_odr().await?;
+        self.set_odr(Odr::Hz760).await?;
+        self.set_bandwidth(Bandwidth::Maximum).await?;
+        self.set_scale(Scale::Dps2000).await?;
+        self.set_block_data_update(true).await?;
+        self.set_fifo_mode(FifoMode::Stream).await?;
+        Ok(())
+    }
+
+    /// Gyroscope measurements delta-encoded against the previous measurement
+    ///
+    /// Returns the per-axis difference to `prev` as `i8`. An axis whose
+    /// difference lies outside of `-127..=127` is set to `DELTA_OVERFLOW`, in
+    /// which case the full measurement should be transmitted instead. `prev` is
+    /// updated with the new measurement.
+    #[bisync]
+    pub async fn gyro_delta_encoded(&mut self, prev: &mut I16x3) -> Result<[i8; 3], Spi::Error> {
+        let gyro = self.gyro().await?;
+        let delta = |new: i16, old: i16| {
+            i8::try_from(new as i32 - old as i32)
+                .ok()
+                .filter(|d| *d != DELTA_OVERFLOW)
+                .unwrap_or(DELTA_OVERFLOW)
+        };
+        let encoded = [
+            delta(gyro.x, prev.x),
+            delta(gyro.y, prev.y),
+            delta(gyro.z, prev.z),
+        ];
+        *prev = gyro;
+        Ok(encoded)
+    }
+
+    /// Axis with the largest angular rate magnitude
+    ///
+    /// Returns `None` if the rate on every axis is below
+    /// `DOMINANT_AXIS_THRESHOLD_DPS`.
+    #[bisync]
+    pub async fn dominant_axis(&mut self) -> Result<Option<Axis>, Spi::Error> {
+        let threshold = self
+            .scale
+            .to_raw(DOMINANT_AXIS_THRESHOLD_DPS)
+            .unsigned_abs();
+        let gyro = self.gyro().await?;
+        let (axis, max) = [Axis::X, Axis::Y, Axis::Z]
+            .into_iter()
+            .map(|axis| (axis, gyro.axis(axis).unsigned_abs()))
+            .fold((Axis::X, 0), |a, b| if b.1 > a.1 { b } else { a });
+        Ok((max >= threshold).then_some(axis))
+    }
+
+    /// Get the bias subtracted from gyroscope measurements
+    pub fn bias(&self) -> I16x3 {
+        self.bias.to_i16x3()
+    }
+
+    /// Set the bias subtracted from gyroscope measurements
+    ///
+    /// The bias is in raw LSB and in the sensor frame, i.e. before the sign
+    /// convention is applied. It is zero by default.
+    pub fn set_bias(&mut self, bias: I16x3) -> &mut Self {
+        self.bias = Bias::from_i16x3(bias);
+        self
+    }
+
+    /// Calibrate the bias
+    ///
+    /// Must be called with the sensor at rest. Averages `samples` new
+    /// measurements, waiting for new data before each, and stores the mean
+    /// as the bias subtracted by `gyro`. Returns the bias rounded to LSB, see
+    /// `bias`. A `samples` value of 0 is treated as 1. Returns
+    /// `Error::Timeout` if a wait times out, see `wait_for_data`.
+    #[bisync]
+    pub async fn calibrate(&mut self, samples: u16) -> Result<I16x3, Error<Spi::Error>> {
+        let samples = samples.max(1);
+        let mut sums = [0i64; 3];
+        for _ in 0..samples {
+            self.wait_for_data().await?;
+            let raw = self.gyro_uncorrected().await?;
+            for (sum, val) in sums.iter_mut().zip([raw.x, raw.y, raw.z]) {
+                *sum += val as i64;
+            }
+        }
+        self.bias = Bias::from_sums(sums, samples);
+        Ok(self.bias())
+    }
+
+    /// Track the bias while the sensor is at rest
+    ///
+    /// Reads a measurement and, if the bias corrected rate is below
+    /// `idle_threshold` (raw LSB) on all axes, moves the stored bias by 1/64
+    /// of the difference towards the measurement. Returns whether the bias was
+    /// updated.
+    #[bisync]
+    pub async fn update_bias_if_idle(&mut self, idle_threshold: i16) -> Result<bool, Spi::Error> {
+        let raw = self.gyro_uncorrected().await?;
+        if self.bias.apply(raw).abs_max() >= idle_threshold.unsigned_abs() {
+            return Ok(false);
+        }
+        self.bias.track(raw, BIAS_TRACKING_SHIFT);
+        Ok(true)
+    }
+
+    /// Check the `WHO_AM_I` register against an expected value
+    ///
+    /// Returns `Error::WrongDevice` with the read value on mismatch. Unlike
+    /// `model`, this also works for relabeled or otherwise non-standard IDs.
+    #[bisync]
+    pub async fn expect_who_am_i(&mut self, expected: u8) -> Result<(), Error<Spi::Error>> {
+        let found = self.who_am_i().await?;
+        if found != expected {
+            return Err(Error::WrongDevice(found));
+        }
+        Ok(())
+    }
+
+    /// Drain the FIFO and log every sample with `defmt`
+    ///
+    /// Returns the number of logged samples.
+    #[cfg(feature = "defmt")]
+    #[bisync]
+    pub async fn fifo_log(&mut self) -> Result<usize, Spi::Error> {
+        let mut samples = [I16x3 { x: 0, y: 0, z: 0 }; FIFO_DEPTH];
+        let count = self.read_fifo(&mut samples).await?;
+        for sample in &samples[..count] {
+            defmt::info!("l3gd20: {}", sample);
+        }
+        Ok(count)
+    }
+
+    /// Gyroscope measurements which are valid after a configuration change
+    ///
+    /// If a control register was written since the last call of
+    /// `gyro_settled`, waits `SETTLING_PERIODS` output data periods for the
+    /// filters to settle before reading. Otherwise reads immediately. Other
+    /// reads, e.g. `gyro`, `all` or from the FIFO, do not count, so the first
+    /// call after a configuration change always waits.
+    #[bisync]
+    pub async fn gyro_settled(&mut self, delay: &mut impl DelayNs) -> Result<I16x3, Spi::Error> {
+        if self.dirty {
+            let period_us = self.odr().await?.period_us();
+            delay.delay_us(SETTLING_PERIODS * period_us).await;
+            self.dirty = false;
+        }
+        self.gyro().await
+    }
+
+    /// Feed a gyroscope measurement into an Allan variance accumulator
+    ///
+    /// Call this at the output data rate with the sensor at rest; the
+    /// caller-owned `acc` can then be queried for the Allan variance or
+    /// deviation at several cluster lengths.
+    #[bisync]
+    pub async fn gyro_allan_update<const N: usize>(
+        &mut self,
+        acc: &mut AllanAccumulator<N>,
+    ) -> Result<(), Spi::Error> {
+        let gyro = self.gyro().await?;
+        acc.push(gyro);
+        Ok(())
+    }
+
+    /// Get the interrupt pin configuration
+    #[bisync]
+    pub async fn ctrl3(&mut self) -> Result<Ctrl3Config, Spi::Error> {
+        let reg3 = self.read_register(Register::CTRL_REG3).await?;
+        Ok(Ctrl3Config::from_u8(reg3))
+    }
+
+    /// Set the interrupt pin configuration
+    ///
+    /// Writes all of `CTRL_REG3`, e.g. set `Ctrl3Config::drdy` to signal new
+    /// data on the DRDY/INT2 pin instead of polling `status`.
+    /// `force_interrupt_test` and `BatchReader` modify single bits of the
+    /// register while they run.
+    #[bisync]
+    pub async fn set_ctrl3(&mut self, cfg: Ctrl3Config) -> Result<&mut Self, Spi::Error> {
+        self.write_register(Register::CTRL_REG3, cfg.value())
+            .await?;
+        Ok(self)
+    }
+
+    /// Get the interrupt 1 generator configuration
+    #[bisync]
+    pub async fn int1_config(&mut self) -> Result<Int1Config, Spi::Error> {
+        let cfg = self.read_register(Register::INT1_CFG).await?;
+        Ok(Int1Config::from_u8(cfg))
+    }
+
+    /// Set the interrupt 1 generator configuration
+    ///
+    /// Writes `INT1_CFG`. The thresholds and the duration are configured
+    /// separately and the interrupt must be routed to the INT1 pin in
+    /// `CTRL_REG3` to drive the pin.
+    #[bisync]
+    pub async fn set_int1_config(&mut self, cfg: Int1Config) -> Result<&mut Self, Spi::Error> {
+        self.write_register(Register::INT1_CFG, cfg.value()).await?;
+        Ok(self)
+    }
+
+    /// Get the interrupt 1 threshold of `axis` in raw LSB
+    #[bisync]
+    pub async fn int1_threshold(&mut self, axis: Axis) -> Result<u16, Spi::Error> {
+        let (high, low) = axis.int1_threshold_registers();
+        let high = self.read_register(high).await?;
+        let low = self.read_register(low).await?;
+        Ok(u16::from_be_bytes([high, low]) & INT1_THRESHOLD_MAX)
+    }
+
+    /// Set the interrupt 1 threshold of `axis` in raw LSB
+    ///
+    /// The threshold is compared to the magnitude of the rate at the current
+    /// `Scale`, see `set_int1_threshold_dps`. It saturates at 32767.
+    #[bisync]
+    pub async fn set_int1_threshold(
+        &mut self,
+        axis: Axis,
+        threshold: u16,
+    ) -> Result<&mut Self, Spi::Error> {
+        let [high, low] = threshold.min(INT1_THRESHOLD_MAX).to_be_bytes();
+        let (high_reg, low_reg) = axis.int1_threshold_registers();
+        self.write_register(high_reg, high).await?;
+        self.write_register(low_reg, low).await?;
+        Ok(self)
+    }
+
+    /// Set the interrupt 1 threshold of `axis` in degrees per second
+    ///
+    /// Converts `dps` with the current `Scale`. The conversion is only valid
+    /// until the scale is changed.
+    #[bisync]
+    pub async fn set_int1_threshold_dps(
+        &mut self,
+        axis: Axis,
+        dps: f32,
+    ) -> Result<&mut Self, Spi::Error> {
+        let threshold = self.scale.to_raw(dps.abs()) as u16;
+        self.set_int1_threshold(axis, threshold).await
+    }
+
+    /// Get the interrupt 1 duration in samples and whether `WAIT` is set
+    #[bisync]
+    pub async fn int1_duration(&mut self) -> Result<(u8, bool), Spi::Error> {
+        let duration = self.read_register(Register::INT1_DURATION).await?;
+        Ok((
+            duration & INT1_DURATION_D,
+            duration & INT1_DURATION_WAIT != 0,
+        ))
+    }
+
+    /// Set the interrupt 1 duration and the `WAIT` bit
+    ///
+    /// An event must last `samples` output data periods before the interrupt
+    /// is raised, so the effective time depends on the configured `Odr`. With
+    /// `wait` set, the interrupt is also only cleared after the rate has been
+    /// back within the thresholds for the same duration. `samples` saturates
+    /// at 127.
+    #[bisync]
+    pub async fn set_int1_duration(
+        &mut self,
+        samples: u8,
+        wait: bool,
+    ) -> Result<&mut Self, Spi::Error> {
+        let wait = if wait { INT1_DURATION_WAIT } else { 0 };
+        self.write_register(Register::INT1_DURATION, wait | samples.min(INT1_DURATION_D))
+            .await?;
+        Ok(self)
+    }
+
+    /// Set the interrupt 1 duration in milliseconds
+    ///
+    /// Converts `ms` to the nearest number of samples at the current output
+    /// data rate, saturating at 127 samples. The `WAIT` bit is preserved. The
+    /// conversion is only valid until the data rate is changed.
+    #[bisync]
+    pub async fn set_int1_duration_ms(&mut self, ms: u16) -> Result<(), Spi::Error> {
+        let samples = self.odr().await?.duration_samples(ms);
+        let duration = self.read_register(Register::INT1_DURATION).await?;
+        self.write_register(
+            Register::INT1_DURATION,
+            (duration & !INT1_DURATION_D) | samples,
+        )
+        .await
+    }
+
+    /// Read the angular rate as input for a complementary filter
+    ///
+    /// The rate is converted to degrees per second using the current `Scale`
+    /// and integrated over `dt_s` seconds into a per-axis angle change.
+    #[bisync]
+    pub async fn gyro_for_complementary(
+        &mut self,
+        dt_s: f32,
+    ) -> Result<ComplementaryInput, Spi::Error> {
+        let scale = self.scale;
+        let gyro = self.gyro().await?;
+        let rate = self.rate_dps(scale, gyro);
+        Ok(ComplementaryInput {
+            rate_dps: rate,
+            angle_delta_deg: F32x3 {
+                x: rate.x * dt_s,
+                y: rate.y * dt_s,
+                z: rate.z * dt_s,
+            },
+            dt_s,
+        })
+    }
+
+    /// Check for a stuck bus
+    ///
+    /// Reads `WHO_AM_I` and the temperature, status and output registers in
+    /// one burst. Returns `true` if all of them read as `0x00` or all as
+    /// `0xFF`, which a connected sensor never produces but a floating or
+    /// shorted data line does.
+    #[bisync]
+    pub async fn detect_bus_fault(&mut self) -> Result<bool, Spi::Error> {
+        let who_am_i = self.who_am_i().await?;
+        let mut bytes = [0u8; 9];
+        self.read_many(Register::OUT_TEMP, &mut bytes).await?;
+
+        Ok(matches!(who_am_i, 0x00 | 0xFF) && bytes[1..].iter().all(|&b| b == who_am_i))
+    }
+
+    /// Gyroscope measurements decoded in the given byte order
+    ///
+    /// Ignores the `BLE` bit of `CTRL_REG4` and decodes the output registers
+    /// with the high byte first if `big_endian` is set, and the low byte
+    /// first otherwise. Decoding the same data both ways helps diagnose byte
+    /// order mismatches. Bias and sign correction are applied as in `gyro`.
+    #[bisync]
+    pub async fn gyro_with_endian(&mut self, big_endian: bool) -> Result<I16x3, Spi::Error> {
+        let mut bytes = [0u8; 7];
+        self.read_many(Register::OUT_X_L, &mut bytes).await?;
+
+        let raw = if big_endian {
+            I16x3::from_bytes_be(&bytes[1..])
+        } else {
+            I16x3::from_bytes(&bytes[1..])
+        };
+        Ok(self.correct(raw))
+    }
+
+    /// Verify that the high-pass filter removes the DC component
+    ///
+    /// Must be called with the sensor at rest. Returns `false` if the
+    /// high-pass filter is disabled or bypassed in `CTRL_REG5`. Otherwise
+    /// averages `HP_FILTER_VERIFICATION_SAMPLES` measurements, one per output
+    /// data period, at the start and again at the end of a window of three
+    /// times that length. Returns `true` if the late mean of every axis is within
+    /// `HP_FILTER_RESIDUAL_DPS` of zero or at most half the early mean,
+    /// i.e. the output converges toward zero. The stored bias is not
+    /// subtracted.
+    #[bisync]
+    pub async fn verify_hp_filter(&mut self, delay: &mut impl DelayNs) -> Result<bool, Spi::Error> {
+        let reg5 = self.read_register(Register::CTRL_REG5).await?;
+        if reg5 & CTRL_REG5_HPEN == 0 || reg5 & CTRL_REG5_OUT_SEL == 0 {
+            return Ok(false);
+        }
+        let scale = self.scale;
+        let period_us = self.odr().await?.period_us();
+
+        let mut early = VarWindow::<HP_FILTER_VERIFICATION_SAMPLES>::new();
+        let mut late = VarWindow::<HP_FILTER_VERIFICATION_SAMPLES>::new();
+        for _ in 0..HP_FILTER_VERIFICATION_SAMPLES {
+            delay.delay_us(period_us).await;
+            early.push(self.gyro_uncorrected().await?);
+        }
+        for _ in 0..HP_FILTER_VERIFICATION_SAMPLES {
+            delay.delay_us(period_us).await;
+        }
+        for _ in 0..HP_FILTER_VERIFICATION_SAMPLES {
+            delay.delay_us(period_us).await;
+            late.push(self.gyro_uncorrected().await?);
+        }
+
+        let (early, late) = (early.mean(), late.mean());
+        let converged = |early: f32, late: f32| {
+            let late = (late * scale.dps_per_lsb()).abs();
+            late <= HP_FILTER_RESIDUAL_DPS || late <= (early * scale.dps_per_lsb()).abs() / 2.0
+        };
+        Ok(converged(early.x, late.x) && converged(early.y, late.y) && converged(early.z, late.z))
+    }
+
+    /// Read the angular rate and integrate it into the angles held by the driver
+    ///
+    /// Uses rectangular integration over `dt` seconds, see
+    /// `gyro_integrate_into` for a custom `Integrator`. Returns the
+    /// integrated angles in degrees.
+    #[bisync]
+    pub async fn gyro_integrate(&mut self, dt: f32) -> Result<F32x3, Spi::Error> {
+        let mut angles = self.angles;
+        self.gyro_integrate_into(&mut angles, dt).await?;
+        self.angles = angles;
+        Ok(angles)
+    }
+
+    /// Angles in degrees integrated by `gyro_integrate` since the last reset
+    pub fn integrated_angles(&self) -> F32x3 {
+        self.angles
+    }
+
+    /// Reset the angles integrated by `gyro_integrate` to zero
+    pub fn reset_integration(&mut self) {
+        self.angles = F32x3::default();
+    }
+
+    /// Get the self-heating bias model used by `gyro_thermal_tracked`
+    pub fn thermal_model(&self) -> ThermalModel {
+        self.thermal
+    }
+
+    /// Set the self-heating bias model used by `gyro_thermal_tracked`
+    ///
+    /// The model is zero by default, i.e. no correction is applied.
+    pub fn set_thermal_model(&mut self, model: ThermalModel) -> &mut Self {
+        self.thermal = model;
+        self
+    }
+
+    /// Calibrate the reference point of the self-heating bias model
+    ///
+    /// Must be called with the sensor at rest, typically right after
+    /// power-up. Averages `THERMAL_CALIBRATION_SAMPLES` measurements, one per
+    /// output data period, and stores the mean rate and temperature as the
+    /// reference of the model. The slope is preserved, as it has to be
+    /// characterized beforehand.
+    #[bisync]
+    pub async fn calibrate_thermal_reference(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<ThermalModel, Spi::Error> {
+        let period_us = self.odr().await?.period_us();
+
+        let mut sum = [0i32; 3];
+        let mut temp_sum = 0i32;
+        for _ in 0..THERMAL_CALIBRATION_SAMPLES {
+            delay.delay_us(period_us).await;
+            let measurements = self.all().await?;
+            sum[0] += measurements.gyro.x as i32;
+            sum[1] += measurements.gyro.y as i32;
+            sum[2] += measurements.gyro.z as i32;
+            temp_sum += measurements.temp_celcius_with_offset(self.temp_offset) as i32;
+        }
+        let n = THERMAL_CALIBRATION_SAMPLES as f32;
+        let dps_per_lsb = self.scale.dps_per_lsb();
+        let mean = |sum: i32| sum as f32 / n * dps_per_lsb;
+
+        self.thermal.reference_celcius = temp_sum as f32 / n;
+        self.thermal.bias_dps = F32x3 {
+            x: mean(sum[0]),
+            y: mean(sum[1]),
+            z: mean(sum[2]),
+        };
+        Ok(self.thermal)
+    }
+
+    /// Angular rate in degrees per second compensated for self-heating drift
+    ///
+    /// Reads the gyroscope and the temperature in one burst and subtracts the
+    /// bias predicted by the `ThermalModel` at the current temperature.
+    #[bisync]
+    pub async fn gyro_thermal_tracked(&mut self) -> Result<F32x3, Spi::Error> {
+        let measurements = self.all().await?;
+        let rate = self.scale.degrees_f32x3(measurements.gyro);
+        let bias = self
+            .thermal
+            .bias_at(measurements.temp_celcius_with_offset(self.temp_offset));
+        Ok(self.to_body(F32x3 {
+            x: rate.x - bias.x,
+            y: rate.y - bias.y,
+            z: rate.z - bias.z,
+        }))
+    }
+
+    /// Raw in-place SPI transfer
+    ///
+    /// The caller owns the complete transfer, including the command byte with
+    /// the `READ`/`WRITE` and `MULTI` bits, and `buf` receives the bytes
+    /// clocked in from the sensor. Nothing is validated: a malformed command
+    /// can write arbitrary registers, including the reserved ones which must
+    /// not be modified, and bypasses the state tracked by the driver.
+    #[bisync]
+    pub async fn transfer(&mut self, buf: &mut [u8]) -> Result<(), Spi::Error> {
+        self.spi.transfer_in_place(buf).await
+    }
+
+    /// Log periodic raw temperature readings
+    ///
+    /// Reads `samples` temperature measurements, at most `out.len()`, one per
+    /// output data period, and returns the number of readings written to
+    /// `out`. Use a slower output data rate or call repeatedly with a delay in
+    /// between for longer profiles, e.g. during a thermal chamber sweep.
+    #[bisync]
+    pub async fn temp_profile(
+        &mut self,
+        samples: u16,
+        delay: &mut impl DelayNs,
+        out: &mut [i8],
+    ) -> Result<usize, Spi::Error> {
+        let period_us = self.odr().await?.period_us();
+        let count = out.len().min(samples as usize);
+        for temp in &mut out[..count] {
+            delay.delay_us(period_us).await;
+            *temp = self.temp_raw().await?;
+        }
+        Ok(count)
+    }
+
+    /// Whether the current scale resolves the rate `dps`
+    ///
+    /// Returns `true` if `dps` converts to a nonzero raw value with the
+    /// current `Scale`, i.e. it is at least half an LSB. Slower rotations are
+    /// lost in quantization and require a more sensitive scale.
+    #[bisync]
+    pub async fn resolves_rate(&mut self, dps: f32) -> Result<bool, Spi::Error> {
+        Ok(self.scale.to_raw(dps) != 0)
+    }
+
+    /// Compact descriptor of the configuration
+    ///
+    /// Returns `CTRL_REG1` to `CTRL_REG5` as-is, read in one burst. Write it
+    /// back, e.g. on another device, with `apply_descriptor`.
+    #[bisync]
+    pub async fn config_descriptor(&mut self) -> Result<[u8; 5], Spi::Error> {
+        let mut bytes = [0u8; 6];
+        self.read_many(Register::CTRL_REG1, &mut bytes).await?;
+
+        let mut descriptor = [0u8; 5];
+        descriptor.copy_from_slice(&bytes[1..]);
+        Ok(descriptor)
+    }
+
+    /// Write a descriptor created by `config_descriptor` in one burst
+    #[bisync]
+    pub async fn apply_descriptor(&mut self, d: &[u8; 5]) -> Result<(), Spi::Error> {
+        let mut buffer = [0u8; 6];
+        buffer[0] = Register::CTRL_REG1.addr() | MULTI | WRITE;
+        buffer[1..].copy_from_slice(d);
+        self.spi.write(&buffer).await?;
+
+        self.track_ctrl_reg1(d[0]);
+        self.track_ctrl_reg4(d[3]);
+        Ok(())
+    }
+
+    /// Gyroscope measurements which were not overrun
+    ///
+    /// Reads status and data in one burst, see `all_detailed`. If an overrun
+    /// is flagged, the sample may mix data of consecutive periods and is read
+    /// again, up to `max_retries` times. Returns the last sample if every
+    /// attempt was overrun.
+    #[bisync]
+    pub async fn gyro_clean(&mut self, max_retries: u8) -> Result<I16x3, Spi::Error> {
+        let mut attempts = 0;
+        loop {
+            let (measurements, status) = self.all_detailed().await?;
+            if !status.overrun || attempts == max_retries {
+                return Ok(measurements.gyro);
+            }
+            attempts += 1;
+        }
+    }
+
+    /// Gyroscope measurements acquired after the call began
+    ///
+    /// Waits for `drdy` to go high and discards the pending sample, which
+    /// may have been acquired before the call, then waits for the next
+    /// data-ready signal and reads the fresh sample.
+    ///
+    /// The data-ready signal must be routed to the DRDY/INT2 pin (`I2_DRDY`
+    /// in `CTRL_REG3`) with the default active-high polarity. Returns
+    /// `Error::Timeout` if the pin is not high within `DATA_READY_POLLS`
+    /// polls.
+    #[bisync]
+    pub async fn gyro_guaranteed_fresh(
+        &mut self,
+        drdy: &mut impl InputPin,
+    ) -> Result<I16x3, Error<Spi::Error>> {
+        Self::wait_for_drdy(drdy).await?;
+        self.gyro_uncorrected().await?;
+        Self::wait_for_drdy(drdy).await?;
+        Ok(self.gyro().await?)
+    }
+
+    /// Poll `drdy` until it is high, at most `DATA_READY_POLLS` times
+    #[only_sync]
+    fn wait_for_drdy(drdy: &mut impl InputPin) -> Result<(), Error<Spi::Error>> {
+        for _ in 0..DATA_READY_POLLS {
+            if drdy.is_high().map_err(|_| Error::Pin)? {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Poll `drdy` until it is high, at most `DATA_READY_POLLS` times
+    ///
+    /// Yields to the executor between polls.
+    #[only_async]
+    async fn wait_for_drdy(drdy: &mut impl InputPin) -> Result<(), Error<Spi::Error>> {
+        for _ in 0..DATA_READY_POLLS {
+            if drdy.is_high().map_err(|_| Error::Pin)? {
+                return Ok(());
+            }
+            YieldNow::default().await;
+        }
+        Err(Error::Timeout)
+    }
+
+    /// Read the interrupt 1 source
+    ///
+    /// Reading `INT1_SRC` clears a latched interrupt, so the flags are only
+    /// reported once.
+    #[bisync]
+    pub async fn int1_source(&mut self) -> Result<Int1Source, Spi::Error> {
+        let src = self.read_register(Register::INT1_SRC).await?;
+        Ok(Int1Source::from_u8(src))
+    }
+
+    /// Axis and direction which triggered interrupt 1
+    ///
+    /// Reads `INT1_SRC`, which clears a latched interrupt. Returns `None` if
+    /// no interrupt is active. If several events are flagged, the first one
+    /// in X, Y, Z order is reported, with the high event before the low one.
+    #[bisync]
+    pub async fn int1_trigger(&mut self) -> Result<Option<(Axis, Direction)>, Spi::Error> {
+        let src = self.read_register(Register::INT1_SRC).await?;
+        Ok(Direction::from_int1_src(src))
+    }
+
+    /// Switch between 4-wire and 3-wire SPI
+    ///
+    /// Sets (`enable`) or clears the `SIM` bit of `CTRL_REG4`. In 3-wire mode
+    /// SDI/SDO share a single data line whose direction the sensor switches
+    /// for reads; the register protocol stays the same. The host SPI
+    /// peripheral must be reconfigured for half-duplex operation right after
+    /// enabling 3-wire mode, otherwise further transfers fail.
+    #[bisync]
+    pub async fn set_spi_3wire(&mut self, enable: bool) -> Result<&mut Self, Spi::Error> {
+        let reg4 = self.read_register(Register::CTRL_REG4).await?;
+        let reg4 = if enable {
+            reg4 | CTRL_REG4_SIM
+        } else {
+            reg4 & !CTRL_REG4_SIM
+        };
+        self.write_register(Register::CTRL_REG4, reg4).await?;
+        Ok(self)
+    }
+
+    /// Read arbitrary register.
+    ///
+    /// Escape hatch for settings not covered by the rest of the API. The SPI
+    /// read and single byte bits are set internally.
+    #[bisync]
+    pub async fn read_register(&mut self, reg: Register) -> Result<u8, Spi::Error> {
+        let mut buffer = [reg.addr() | SINGLE | READ, 0];
+        self.spi.transfer_in_place(&mut buffer).await?;
+
+        Ok(buffer[1])
+    }
+
+    /// Read multiple bytes starting from the `start_reg` register.
+    /// This function will attempt to fill the provided buffer.
+    #[bisync]
+    pub async fn read_many(
+        &mut self,
+        start_reg: Register,
+        buffer: &mut [u8],
+    ) -> Result<(), Spi::Error> {
+        buffer[0] = start_reg.addr() | MULTI | READ;
+        self.spi.transfer_in_place(buffer).await?;
+
+        Ok(())
+    }
+
+    /// Write arbitrary register.
+    ///
+    /// Escape hatch for settings not covered by the rest of the API. The SPI
+    /// write and single byte bits are set internally. Writing a read-only
+    /// register has no effect on the sensor. Settings which the driver tracks
+    /// itself, such as the enabled axes, may be overwritten by later calls.
+    #[bisync]
+    pub async fn write_register(&mut self, reg: Register, byte: u8) -> Result<(), Spi::Error> {
+        let buffer = [reg.addr() | SINGLE | WRITE, byte];
+        self.spi.write(&buffer).await?;
+        if reg.affects_output() {
+            self.dirty = true;
+        }
+        if let Register::CTRL_REG4 = reg {
+            self.track_ctrl_reg4(byte);
+        }
+
+        Ok(())
+    }
+
+    /// Update the cached byte order and scale after `CTRL_REG4` was written
+    fn track_ctrl_reg4(&mut self, reg4: u8) {
+        self.big_endian = reg4 & CTRL_REG4_BLE != 0;
+        self.scale = Scale::from_u8(reg4);
+    }
+
+    /// Update the driver state after `CTRL_REG1` was written in a burst
+    fn track_ctrl_reg1(&mut self, reg1: u8) {
+        // Keep the axes to wake up with if the sensor was put to sleep
+        if reg1 & CTRL_REG1_AXES != 0 {
+            self.axes = reg1 & CTRL_REG1_AXES;
+        }
+        self.dirty = true;
+    }
+
+    /// Burst read `out.len()` samples, at most `FIFO_DEPTH`, from the FIFO
+    #[bisync]
+    async fn read_fifo_samples(&mut self, out: &mut [I16x3]) -> Result<(), Spi::Error> {
+        let len = out.len().min(FIFO_DEPTH) * 6;
+        // The register address wraps around from OUT_Z_H to OUT_X_L in FIFO mode
+        let mut bytes = [0u8; FIFO_DEPTH * 6 + 1];
+        self.read_many(Register::OUT_X_L, &mut bytes[..len + 1])
+            .await?;
+        for (sample, chunk) in out.iter_mut().zip(bytes[1..len + 1].chunks_exact(6)) {
+            *sample = self.correct(self.decode(chunk));
+        }
+        Ok(())
+    }
+
+    /// Decode `STATUS_REG`, applying status masking, and update the cached status
+    fn decode_status(&mut self, raw: u8) -> Status {
+        let mut sts = Status::from_u8(raw);
+        if self.status_masking {
+            sts = sts.masked(self.axes);
+        }
+        self.last_status = sts;
+        sts
+    }
+
+    /// Rate in dps of a measurement, with the scale factor correction and in
+    /// the body frame
+    fn rate_dps(&self, scale: Scale, raw: I16x3) -> F32x3 {
+        self.to_body(scale.degrees_f32x3(raw))
+    }
+
+    /// Apply the scale factor correction and the mount matrix to a rate in
+    /// the sensor frame
+    fn to_body(&self, rate: F32x3) -> F32x3 {
+        F32x3 {
+            x: rate.x * self.scale_correction[0],
+            y: rate.y * self.scale_correction[1],
+            z: rate.z * self.scale_correction[2],
+        }
+        .transformed(&self.mount)
+    }
+
+    /// Decode the output registers in the configured byte order
+    fn decode(&self, bytes: &[u8]) -> I16x3 {
+        I16x3::from_bytes_ordered(bytes, self.big_endian)
+    }
+
+    /// Subtract the bias and apply the sign convention
+    fn correct(&self, raw: I16x3) -> I16x3 {
+        self.bias.apply(raw).inverted(self.invert)
+    }
+
+    /// Change configuration in register
+    ///
+    /// Helper function to update a particular part of a register without
+    /// affecting other parts of the register that might contain desired
+    /// configuration. This allows the `L3gd20` struct to be used like
+    /// a builder interface when configuring specific parameters.
+    #[bisync]
+    pub async fn change_config<B: BitValue>(
+        &mut self,
+        reg: Register,
+        bits: B,
+    ) -> Result<&mut Self, Spi::Error> {
+        // Create bit mask from width and shift of value
+        let mask = B::mask() << B::shift();
+        // Extract the value as u8
+        let bits = (bits.value() << B::shift()) & mask;
+        // Read current value of register
+        let current = self.read_register(reg).await?;
+        // Use supplied mask so we don't affect more than necessary
+        let masked = current & !mask;
+        // Use `or` to apply the new value without affecting other parts
+        let new_reg = masked | bits;
+        self.write_register(reg, new_reg).await?;
+        Ok(self)
+    }
+}
+
+#[bisync]
+impl<Spi: SpiDevice> Gyroscope for L3gd20<Spi> {
+    type Error = Spi::Error;
+
+    #[bisync]
+    async fn gyro(&mut self) -> Result<I16x3, Spi::Error> {
+        L3gd20::gyro(self).await
+    }
+
+    #[bisync]
+    async fn all(&mut self) -> Result<Measurements, Spi::Error> {
+        L3gd20::all(self).await
+    }
+
+    #[bisync]
+    async fn temp_raw(&mut self) -> Result<i8, Spi::Error> {
+        L3gd20::temp_raw(self).await
+    }
+
+    #[bisync]
+    async fn status(&mut self) -> Result<Status, Spi::Error> {
+        L3gd20::status(self).await
+    }
+
+    #[bisync]
+    async fn who_am_i(&mut self) -> Result<u8, Spi::Error> {
+        L3gd20::who_am_i(self).await
+    }
+
+    #[bisync]
+    async fn odr(&mut self) -> Result<Odr, Spi::Error> {
+        L3gd20::odr(self).await
+    }
+
+    #[bisync]
+    async fn set_odr(&mut self, odr: Odr) -> Result<&mut Self, Spi::Error> {
+        L3gd20::set_odr(self, odr).await
+    }
+
+    #[bisync]
+    async fn bandwidth(&mut self) -> Result<Bandwidth, Spi::Error> {
+        L3gd20::bandwidth(self).await
+    }
+
+    #[bisync]
+    async fn set_bandwidth(&mut self, bw: Bandwidth) -> Result<&mut Self, Spi::Error> {
+        L3gd20::set_bandwidth(self, bw).await
+    }
+
+    #[bisync]
+    async fn scale(&mut self) -> Result<Scale, Spi::Error> {
+        L3gd20::scale(self).await
+    }
+
+    #[bisync]
+    async fn set_scale(&mut self, scale: Scale) -> Result<&mut Self, Spi::Error> {
+        L3gd20::set_scale(self, scale).await
+    }
+}
+
+/// FIFO acquisition session
+///
+/// Enables the FIFO in Stream mode on construction and drains it in batches
+/// once the watermark is reached. Call `close` to return to Bypass mode.
+pub struct FifoSession<'a, Spi> {
+    l3gd20: &'a mut L3gd20<Spi>,
+}
+
+#[bisync]
+impl<'a, Spi: SpiDevice> FifoSession<'a, Spi> {
+    /// Enable the FIFO in Stream mode with the given watermark level
+    ///
+    /// `watermark` saturates at 31.
+    #[bisync]
+    pub async fn new(l3gd20: &'a mut L3gd20<Spi>, watermark: u8) -> Result<Self, Spi::Error> {
+        // Bypass mode first, which also discards stale samples
+        l3gd20
+            .write_register(Register::FIFO_CTRL_REG, watermark.min(FIFO_CTRL_WTM))
+            .await?;
+        l3gd20.set_fifo_mode(FifoMode::Stream).await?;
+        Ok(FifoSession { l3gd20 })
+    }
+
+    /// Drain the FIFO into `out` once the watermark is reached
+    ///
+    /// Returns the number of samples written to `out`, which is 0 if the
+    /// watermark has not been reached yet. Samples which do not fit into
+    /// `out` stay in the FIFO.
+    #[bisync]
+    pub async fn poll(&mut self, out: &mut [I16x3]) -> Result<usize, Spi::Error> {
+        let status = self.l3gd20.fifo_status().await?;
+        if !status.watermark {
+            return Ok(0);
+        }
+        let count = (status.stored as usize).min(out.len());
+        if count == 0 {
+            return Ok(0);
+        }
+
+        self.l3gd20.read_fifo_samples(&mut out[..count]).await?;
+        Ok(count)
+    }
+
+    /// Return to Bypass mode and disable the FIFO
+    #[bisync]
+    pub async fn close(self) -> Result<(), Spi::Error> {
+        self.l3gd20.set_fifo_mode(FifoMode::Bypass).await?;
+        Ok(())
+    }
+}
+
+/// Batched FIFO reader driven by the watermark interrupt
+///
+/// Routes the FIFO watermark interrupt to the DRDY/INT2 pin and collects the
+/// samples drained on every interrupt into a buffer of `N` samples, so
+/// processing can run at a lower rate than the interrupts.
+pub struct BatchReader<'a, Spi, P, const N: usize> {
+    session: FifoSession<'a, Spi>,
+    pin: P,
+    batch: [I16x3; N],
+    len: usize,
+}
+
+#[bisync]
+impl<'a, Spi: SpiDevice, P: InputPin, const N: usize> BatchReader<'a, Spi, P, N> {
+    /// Enable the FIFO in Stream mode with the given watermark level and
+    /// route the watermark interrupt to `pin`
+    ///
+    /// `pin` must be connected to DRDY/INT2, which is active high unless
+    /// configured otherwise. `watermark` saturates at 31.
+    #[bisync]
+    pub async fn new(
+        l3gd20: &'a mut L3gd20<Spi>,
+        pin: P,
+        watermark: u8,
+    ) -> Result<Self, Spi::Error> {
+        // Only route the interrupt once the FIFO is set up
+        let session = FifoSession::new(l3gd20, watermark).await?;
+        let routed = match session.l3gd20.read_register(Register::CTRL_REG3).await {
+            Ok(reg3) => {
+                session
+                    .l3gd20
+                    .write_register(Register::CTRL_REG3, reg3 | CTRL_REG3_I2_WTM)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = routed {
+            // Best effort, the routing error is reported
+            let _ = session.close().await;
+            return Err(e);
+        }
+        Ok(BatchReader {
+            session,
+            pin,
+            batch: [I16x3 { x: 0, y: 0, z: 0 }; N],
+            len: 0,
+        })
+    }
+
+    /// Drain the FIFO into the batch if the interrupt pin is asserted
+    ///
+    /// Call this from the interrupt handler or whenever the pin may have
+    /// been asserted. Returns the number of samples added to the batch.
+    /// Samples which do not fit into the batch stay in the FIFO until the
+    /// batch is taken.
+    #[bisync]
+    pub async fn poll(&mut self) -> Result<usize, Error<Spi::Error>> {
+        if !self.pin.is_high().map_err(|_| Error::Pin)? {
+            return Ok(0);
+        }
+        let count = self.session.poll(&mut self.batch[self.len..]).await?;
+        self.len += count;
+        Ok(count)
+    }
+
+    /// Number of samples in the batch
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the batch contains no samples
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Take the collected samples, oldest first, and start a new batch
+    pub fn take_batch(&mut self) -> &[I16x3] {
+        let len = core::mem::take(&mut self.len);
+        &self.batch[..len]
+    }
+
+    /// Remove the interrupt routing and return the FIFO to Bypass mode
+    #[bisync]
+    pub async fn close(self) -> Result<(), Spi::Error> {
+        let l3gd20 = &mut *self.session.l3gd20;
+        let reg3 = l3gd20.read_register(Register::CTRL_REG3).await?;
+        l3gd20
+            .write_register(Register::CTRL_REG3, reg3 & !CTRL_REG3_I2_WTM)
+            .await?;
+        self.session.close().await
+    }
+}
+
+#[only_async]
+impl<Spi: SpiDevice> L3gd20<Spi> {
+    /// Release the output registers after a cancelled read
+    ///
+    /// Dropping a `gyro` future, e.g. in a `select`, may abort the transfer
+    /// after only some of the output registers were read. With block data
+    /// update enabled the sensor then holds the output registers until the
+    /// remaining bytes are read, so subsequent measurements are stale. This
+    /// reads all output registers once and discards them. Call it before
+    /// reading again after a read was cancelled.
+    pub async fn recover_after_cancel(&mut self) -> Result<(), Spi::Error> {
+        let mut bytes = [0u8; 7];
+        self.read_many(Register::OUT_X_L, &mut bytes).await?;
+        Ok(())
+    }
+}
+
+#[only_async]
+#[cfg(feature = "futures")]
+impl<Spi: SpiDevice> L3gd20<Spi> {
+    /// Stream of gyroscope measurements
+    ///
+    /// The returned stream yields the measurements of `gyro_decimated`, i.e.
+    /// it waits for new data on any axis, yielding to the executor between
+    /// polls of `STATUS_REG`, and applies the decimation factor. Bus errors
+    /// are yielded as items. The stream ends if no new data is available
+    /// within `DATA_READY_POLLS` polls, e.g. because the sensor is powered
+    /// down or asleep.
+    pub fn gyro_stream<'a>(
+        &'a mut self,
+    ) -> GyroStream<'a, Spi, impl core::future::Future<Output = StreamStep<'a, Spi>> + 'a> {
+        GyroStream {
+            l3gd20: Some(self),
+            pending: None,
+            step: Self::stream_step,
+        }
+    }
+
+    /// Read the next item of `gyro_stream`
+    async fn stream_step(l3gd20: &mut Self) -> StreamStep<'_, Spi> {
+        let item = match l3gd20.gyro_decimated().await {
+            Ok(gyro) => Some(Ok(gyro)),
+            Err(Error::Bus(e)) => Some(Err(e)),
+            // Waiting for new data timed out, which ends the stream
+            Err(_) => None,
+        };
+        (l3gd20, item)
+    }
+}
+
+/// Driver and item returned by the pending read of a `GyroStream`
+#[only_async]
+#[cfg(feature = "futures")]
+pub type StreamStep<'a, Spi> = (
+    &'a mut L3gd20<Spi>,
+    Option<Result<I16x3, <Spi as embedded_hal_async::spi::ErrorType>::Error>>,
+);
+
+#[only_async]
+#[cfg(feature = "futures")]
+pin_project_lite::pin_project! {
+    /// Stream of gyroscope measurements, see `L3gd20::gyro_stream`
+    ///
+    /// Items are of type `Result<I16x3, E>`, where `E` is the bus error type.
+    /// The stream borrows the driver mutably for `'a`. `Fut` is the type of
+    /// the pending read, which is pinned inside the stream, so the stream
+    /// needs no allocator. It is not `Unpin`, pin it before polling, e.g.
+    /// with `core::pin::pin!`.
+    pub struct GyroStream<'a, Spi, Fut> {
+        l3gd20: Option<&'a mut L3gd20<Spi>>,
+        #[pin]
+        pending: Option<Fut>,
+        step: fn(&'a mut L3gd20<Spi>) -> Fut,
+    }
+}
+
+#[only_async]
+#[cfg(feature = "futures")]
+impl<'a, Spi: SpiDevice, Fut> futures::Stream for GyroStream<'a, Spi, Fut>
+where
+    Fut: core::future::Future<Output = StreamStep<'a, Spi>>,
+{
+    type Item = Result<I16x3, Spi::Error>;
+
+    fn poll_next(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if let Some(l3gd20) = this.l3gd20.take() {
+            this.pending.set(Some((this.step)(l3gd20)));
+        }
+        // The stream has ended
+        let Some(pending) = this.pending.as_mut().as_pin_mut() else {
+            return core::task::Poll::Ready(None);
+        };
+        let (l3gd20, item) = core::task::ready!(pending.poll(cx));
+        this.pending.set(None);
+        if item.is_some() {
+            *this.l3gd20 = Some(l3gd20);
+        }
+        core::task::Poll::Ready(item)
+    }
 }
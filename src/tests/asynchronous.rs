@@ -0,0 +1,175 @@
+//! Tests of the asynchronous drivers
+
+use futures::executor::block_on;
+
+use super::{fake, xyz};
+use crate::asynchronous::spi::{GyroStream, L3gd20};
+use crate::Register;
+
+#[test]
+fn gyro_stream_yields_new_samples() {
+    use futures::StreamExt;
+
+    let (spi, sensor) = fake::spi();
+    block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        {
+            let mut sensor = sensor.borrow_mut();
+            // No new data on the first two polls of the first sample
+            sensor.queue(Register::STATUS_REG, &[0x00, 0x00, 0x0F, 0x0F, 0x0F]);
+            sensor.push_sample([1, 2, 3]);
+            sensor.push_sample([4, 5, 6]);
+            sensor.push_sample([7, 8, 9]);
+        }
+        let mut stream = core::pin::pin!(l3gd20.gyro_stream());
+        for expected in [[1, 2, 3], [4, 5, 6], [7, 8, 9]] {
+            let sample = stream.next().await.unwrap().unwrap();
+            assert_eq!(xyz(sample), expected);
+        }
+    });
+    let polls = sensor
+        .borrow()
+        .reads
+        .iter()
+        .filter(|&&a| a == Register::STATUS_REG as u8)
+        .count();
+    assert_eq!(polls, 5);
+}
+
+#[test]
+fn gyro_stream_is_storable_and_yields_bus_errors() {
+    use futures::StreamExt;
+
+    /// Stage of a processing graph owning its input stream
+    struct Stage<'s, 'a, Fut> {
+        input: core::pin::Pin<&'s mut GyroStream<'a, fake::FakeSpi, Fut>>,
+    }
+
+    let (spi, sensor) = fake::spi();
+    block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        l3gd20.set_decimation(2);
+        {
+            let mut sensor = sensor.borrow_mut();
+            sensor.push_sample([1, 2, 3]);
+            sensor.push_sample([4, 5, 6]);
+        }
+        let input = core::pin::pin!(l3gd20.gyro_stream());
+        let mut stage = Stage { input };
+        let sample = stage.input.next().await.unwrap().unwrap();
+        assert_eq!(xyz(sample), [4, 5, 6]);
+
+        sensor.borrow_mut().fail_next = 1;
+        assert!(matches!(
+            stage.input.next().await,
+            Some(Err(fake::FakeError))
+        ));
+    });
+}
+
+#[test]
+fn gyro_stream_ends_when_no_data_arrives() {
+    use futures::StreamExt;
+
+    let (spi, sensor) = fake::spi();
+    block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        sensor.borrow_mut().set(Register::STATUS_REG, 0x00);
+        let mut stream = core::pin::pin!(l3gd20.gyro_stream());
+        assert!(stream.next().await.is_none());
+        // No further reads once the stream has ended
+        let reads = sensor.borrow().reads.len();
+        assert!(stream.next().await.is_none());
+        assert_eq!(sensor.borrow().reads.len(), reads);
+    });
+    let polls = sensor
+        .borrow()
+        .reads
+        .iter()
+        .filter(|&&a| a == Register::STATUS_REG as u8)
+        .count();
+    assert_eq!(polls, crate::DATA_READY_POLLS as usize);
+}
+
+#[test]
+fn wait_for_data_polls_status_until_ready() {
+    let (spi, sensor) = fake::spi();
+    let gyro = block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        sensor
+            .borrow_mut()
+            .queue(Register::STATUS_REG, &[0x00, 0x00, 0x00, 0x08]);
+        sensor.borrow_mut().set_sample([10, -20, 30]);
+        l3gd20.wait_for_data().await.unwrap();
+        l3gd20.gyro().await.unwrap()
+    });
+    assert_eq!(xyz(gyro), [10, -20, 30]);
+    assert!(sensor.borrow().queue_is_empty(Register::STATUS_REG));
+}
+
+#[test]
+fn wait_for_data_times_out_while_asleep() {
+    let (spi, sensor) = fake::spi();
+    let result = block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        l3gd20.sleep().await.unwrap();
+        sensor.borrow_mut().set(Register::STATUS_REG, 0x00);
+        l3gd20.wait_for_data().await
+    });
+    assert!(matches!(result, Err(crate::Error::Timeout)));
+    let polls = sensor
+        .borrow()
+        .reads
+        .iter()
+        .filter(|&&a| a == Register::STATUS_REG as u8)
+        .count();
+    assert_eq!(polls, crate::DATA_READY_POLLS as usize);
+}
+
+#[test]
+fn recover_after_cancel_releases_held_output_registers() {
+    use futures::FutureExt;
+
+    let (spi, sensor) = fake::spi();
+    block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        l3gd20.set_block_data_update(true).await.unwrap();
+        sensor.borrow_mut().push_sample([1, 2, 3]);
+        assert_eq!(xyz(l3gd20.gyro().await.unwrap()), [1, 2, 3]);
+
+        // Dropped after reading OUT_X, which leaves the outputs held
+        sensor.borrow_mut().stall_after = Some(2);
+        assert!(l3gd20.gyro().now_or_never().is_none());
+        sensor.borrow_mut().push_sample([4, 5, 6]);
+        assert_eq!(xyz(l3gd20.gyro().await.unwrap()), [1, 2, 3]);
+        assert_eq!(xyz(l3gd20.gyro().await.unwrap()), [4, 5, 6]);
+
+        sensor.borrow_mut().stall_after = Some(2);
+        assert!(l3gd20.gyro().now_or_never().is_none());
+        sensor.borrow_mut().push_sample([7, 8, 9]);
+        l3gd20.recover_after_cancel().await.unwrap();
+        assert_eq!(xyz(l3gd20.gyro().await.unwrap()), [7, 8, 9]);
+    });
+}
+
+#[test]
+fn gyro_guaranteed_fresh_yields_while_waiting() {
+    let (spi, sensor) = fake::spi();
+    let mut drdy = fake::FakePin::high_after(2);
+    let gyro = block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        sensor.borrow_mut().push_sample([1, 1, 1]);
+        sensor.borrow_mut().push_sample([2, 2, 2]);
+        l3gd20.gyro_guaranteed_fresh(&mut drdy).await.unwrap()
+    });
+    assert_eq!(xyz(gyro), [2, 2, 2]);
+
+    let mut drdy = fake::FakePin::default();
+    let (spi, _) = fake::spi();
+    let result = block_on(async {
+        let mut l3gd20 = L3gd20::new(spi).await.unwrap();
+        l3gd20.gyro_guaranteed_fresh(&mut drdy).await
+    });
+    assert!(matches!(result, Err(crate::Error::Timeout)));
+    assert_eq!(drdy.polls, crate::DATA_READY_POLLS as usize);
+}
@@ -0,0 +1,466 @@
+//! Simulated sensor and transports for the driver tests
+
+// Not every feature combination uses every fixture
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use crate::{Register, WHO_AM_I_L3GD20};
+
+/// Error returned by the fake transports and pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FakeError;
+
+impl embedded_hal::spi::Error for FakeError {
+    fn kind(&self) -> embedded_hal::spi::ErrorKind {
+        embedded_hal::spi::ErrorKind::Other
+    }
+}
+
+impl embedded_hal::i2c::Error for FakeError {
+    fn kind(&self) -> embedded_hal::i2c::ErrorKind {
+        embedded_hal::i2c::ErrorKind::Other
+    }
+}
+
+impl embedded_hal::digital::Error for FakeError {
+    fn kind(&self) -> embedded_hal::digital::ErrorKind {
+        embedded_hal::digital::ErrorKind::Other
+    }
+}
+
+const CTRL_REG4_BDU: u8 = 1 << 7;
+const CTRL_REG4_BLE: u8 = 1 << 6;
+const CTRL_REG5_FIFO_EN: u8 = 1 << 6;
+
+/// Register file of the simulated sensor
+///
+/// Reads are served from `regs`. Values queued with `queue` are latched into
+/// the register right before it is read, which allows tests to feed a
+/// sequence of samples or status flags. Burst reads wrap around from
+/// `OUT_Z_H` to `OUT_X_L` while the FIFO is enabled, like on the sensor.
+///
+/// With block data update enabled, the first read of an output register
+/// latches a whole queued sample, which is then held until `OUT_Z_H` is read.
+pub struct Sensor {
+    pub regs: [u8; 0x40],
+    queues: [VecDeque<u8>; 0x40],
+    /// Every register written, in order
+    pub writes: Vec<(u8, u8)>,
+    /// Every register read, in order
+    pub reads: Vec<u8>,
+    /// Number of upcoming transactions which fail
+    pub fail_next: usize,
+    /// Fail every transaction
+    pub broken: bool,
+    /// Number of failed transactions
+    pub failures: usize,
+    /// Number of transactions, including failed ones
+    pub transactions: usize,
+    /// Number of bytes after which the next asynchronous SPI transaction
+    /// stops and never completes
+    pub stall_after: Option<usize>,
+    /// Output registers held by block data update
+    held: bool,
+}
+
+impl Default for Sensor {
+    fn default() -> Self {
+        let mut regs = [0u8; 0x40];
+        regs[Register::WHO_AM_I as usize] = WHO_AM_I_L3GD20;
+        regs[Register::CTRL_REG1 as usize] = 0x07;
+        // ZYXDA and all per axis new data flags
+        regs[Register::STATUS_REG as usize] = 0x0F;
+        regs[Register::FIFO_SRC_REG as usize] = 1 << 5;
+        Sensor {
+            regs,
+            queues: core::array::from_fn(|_| VecDeque::new()),
+            writes: Vec::new(),
+            reads: Vec::new(),
+            fail_next: 0,
+            broken: false,
+            failures: 0,
+            transactions: 0,
+            stall_after: None,
+            held: false,
+        }
+    }
+}
+
+impl Sensor {
+    /// Value of `reg`
+    pub fn reg(&self, reg: Register) -> u8 {
+        self.regs[reg as usize]
+    }
+
+    /// Set `reg` to `value`
+    pub fn set(&mut self, reg: Register, value: u8) {
+        self.regs[reg as usize] = value;
+    }
+
+    /// Queue `values` to be returned by the next reads of `reg`
+    pub fn queue(&mut self, reg: Register, values: &[u8]) {
+        self.queues[reg as usize].extend(values);
+    }
+
+    /// Whether all values queued for `reg` were read
+    pub fn queue_is_empty(&self, reg: Register) -> bool {
+        self.queues[reg as usize].is_empty()
+    }
+
+    /// Queue a sample to be returned by the next read of the output registers
+    pub fn push_sample(&mut self, sample: [i16; 3]) {
+        let big_endian = self.reg(Register::CTRL_REG4) & CTRL_REG4_BLE != 0;
+        for (i, value) in sample.iter().enumerate() {
+            let bytes = if big_endian {
+                value.to_be_bytes()
+            } else {
+                value.to_le_bytes()
+            };
+            let addr = Register::OUT_X_L as usize + 2 * i;
+            self.queues[addr].push_back(bytes[0]);
+            self.queues[addr + 1].push_back(bytes[1]);
+        }
+    }
+
+    /// Set the output registers to `sample` for all following reads
+    pub fn set_sample(&mut self, sample: [i16; 3]) {
+        for (i, value) in sample.iter().enumerate() {
+            let addr = Register::OUT_X_L as usize + 2 * i;
+            self.regs[addr..addr + 2].copy_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    /// Number of times `reg` was written
+    pub fn write_count(&self, reg: Register) -> usize {
+        self.writes.iter().filter(|(a, _)| *a == reg as u8).count()
+    }
+
+    /// Last value written to `reg`
+    pub fn last_write(&self, reg: Register) -> Option<u8> {
+        self.writes
+            .iter()
+            .rev()
+            .find(|(a, _)| *a == reg as u8)
+            .map(|(_, v)| *v)
+    }
+
+    fn begin(&mut self) -> Result<(), FakeError> {
+        self.transactions += 1;
+        if self.broken || self.fail_next > 0 {
+            self.fail_next = self.fail_next.saturating_sub(1);
+            self.failures += 1;
+            return Err(FakeError);
+        }
+        Ok(())
+    }
+
+    fn next_addr(&self, addr: u8, multi: bool) -> u8 {
+        if !multi {
+            return addr;
+        }
+        let fifo = self.regs[Register::CTRL_REG5 as usize] & CTRL_REG5_FIFO_EN != 0;
+        if fifo && addr == Register::OUT_Z_H as u8 {
+            Register::OUT_X_L as u8
+        } else {
+            (addr + 1) & 0x3F
+        }
+    }
+
+    fn read(&mut self, mut addr: u8, multi: bool, buf: &mut [u8]) {
+        let outputs = Register::OUT_X_L as u8..=Register::OUT_Z_H as u8;
+        let bdu = self.regs[Register::CTRL_REG4 as usize] & CTRL_REG4_BDU != 0;
+        for byte in buf {
+            if bdu && outputs.contains(&addr) {
+                if !self.held {
+                    for out in outputs.clone() {
+                        if let Some(value) = self.queues[out as usize].pop_front() {
+                            self.regs[out as usize] = value;
+                        }
+                    }
+                }
+                self.held = addr != Register::OUT_Z_H as u8;
+            } else if let Some(value) = self.queues[addr as usize].pop_front() {
+                self.regs[addr as usize] = value;
+            }
+            self.reads.push(addr);
+            *byte = self.regs[addr as usize];
+            addr = self.next_addr(addr, multi);
+        }
+    }
+
+    fn write(&mut self, mut addr: u8, multi: bool, bytes: &[u8]) {
+        for &byte in bytes {
+            self.regs[addr as usize] = byte;
+            self.writes.push((addr, byte));
+            addr = self.next_addr(addr, multi);
+        }
+    }
+}
+
+/// Call `op` again after an error, up to `max_retries` times
+///
+/// Stands in for the retry loop of an application, to show that a driver
+/// call can be repeated after a transient bus error. The error of the last
+/// attempt is returned if every attempt failed.
+pub fn retry<T, E>(max_retries: u8, mut op: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    let mut attempts = 0;
+    loop {
+        match op() {
+            Err(_) if attempts < max_retries => attempts += 1,
+            result => return result,
+        }
+    }
+}
+
+/// Handle to the simulated sensor shared with a transport
+pub type Handle = Rc<RefCell<Sensor>>;
+
+/// SPI device connected to the simulated sensor
+pub struct FakeSpi(pub Handle);
+
+/// I2C bus with the simulated sensor at `addr`
+pub struct FakeI2c {
+    pub sensor: Handle,
+    pub addr: u8,
+    pointer: u8,
+    multi: bool,
+}
+
+/// Simulated sensor on a SPI bus
+pub fn spi() -> (FakeSpi, Handle) {
+    let sensor = Handle::default();
+    (FakeSpi(sensor.clone()), sensor)
+}
+
+/// Simulated sensor on an I2C bus at address `addr`
+pub fn i2c(addr: u8) -> (FakeI2c, Handle) {
+    let sensor = Handle::default();
+    let bus = FakeI2c {
+        sensor: sensor.clone(),
+        addr,
+        pointer: 0,
+        multi: false,
+    };
+    (bus, sensor)
+}
+
+const SPI_READ: u8 = 1 << 7;
+const SPI_MULTI: u8 = 1 << 6;
+const I2C_MULTI: u8 = 1 << 7;
+
+impl FakeSpi {
+    /// Run `operations`, transferring at most `limit` data bytes
+    fn run(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+        mut limit: usize,
+    ) -> Result<(), FakeError> {
+        use embedded_hal::spi::Operation;
+
+        let mut sensor = self.0.borrow_mut();
+        sensor.begin()?;
+        for operation in operations {
+            let (cmd, data): (u8, &mut [u8]) = match operation {
+                Operation::Write(bytes) => {
+                    if let Some((cmd, rest)) = bytes.split_first() {
+                        sensor.write(cmd & 0x3F, cmd & SPI_MULTI != 0, rest);
+                    }
+                    continue;
+                }
+                Operation::TransferInPlace(buf) => match buf.split_first_mut() {
+                    Some((cmd, rest)) => (*cmd, rest),
+                    None => continue,
+                },
+                Operation::Transfer(read, write) => {
+                    let cmd = write.first().copied().unwrap_or(0);
+                    if cmd & SPI_READ == 0 {
+                        sensor.write(cmd & 0x3F, cmd & SPI_MULTI != 0, &write[1..]);
+                        continue;
+                    }
+                    match read.split_first_mut() {
+                        Some((_, rest)) => (cmd, rest),
+                        None => continue,
+                    }
+                }
+                Operation::Read(_) | Operation::DelayNs(_) => continue,
+            };
+            let len = data.len().min(limit);
+            let data = &mut data[..len];
+            limit -= len;
+            if cmd & SPI_READ != 0 {
+                sensor.read(cmd & 0x3F, cmd & SPI_MULTI != 0, data);
+            } else {
+                sensor.write(cmd & 0x3F, cmd & SPI_MULTI != 0, data);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal::spi::ErrorType for FakeSpi {
+    type Error = FakeError;
+}
+
+impl embedded_hal::spi::SpiDevice for FakeSpi {
+    fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), FakeError> {
+        self.run(operations, usize::MAX)
+    }
+}
+
+impl embedded_hal_async::spi::SpiDevice for FakeSpi {
+    async fn transaction(
+        &mut self,
+        operations: &mut [embedded_hal::spi::Operation<'_, u8>],
+    ) -> Result<(), FakeError> {
+        let stall_after = self.0.borrow_mut().stall_after.take();
+        match stall_after {
+            Some(limit) => {
+                self.run(operations, limit)?;
+                core::future::pending().await
+            }
+            None => self.run(operations, usize::MAX),
+        }
+    }
+}
+
+impl FakeI2c {
+    fn run(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), FakeError> {
+        use embedded_hal::i2c::Operation;
+
+        let mut sensor = self.sensor.borrow_mut();
+        sensor.begin()?;
+        if address != self.addr {
+            return Err(FakeError);
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    if let Some((sub, rest)) = bytes.split_first() {
+                        self.pointer = sub & 0x3F;
+                        self.multi = sub & I2C_MULTI != 0;
+                        sensor.write(self.pointer, self.multi, rest);
+                    }
+                }
+                Operation::Read(buf) => sensor.read(self.pointer, self.multi, buf),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl embedded_hal::i2c::ErrorType for FakeI2c {
+    type Error = FakeError;
+}
+
+impl embedded_hal::i2c::I2c for FakeI2c {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), FakeError> {
+        self.run(address, operations)
+    }
+}
+
+impl embedded_hal_async::i2c::I2c for FakeI2c {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [embedded_hal::i2c::Operation<'_>],
+    ) -> Result<(), FakeError> {
+        self.run(address, operations)
+    }
+}
+
+/// Delay which returns immediately and records the requested time
+#[derive(Default)]
+pub struct NoDelay {
+    pub total_ns: u64,
+}
+
+impl embedded_hal::delay::DelayNs for NoDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.total_ns += ns as u64;
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.total_ns += ns as u64;
+    }
+}
+
+/// Input pin returning the queued levels, then `level`
+#[derive(Default)]
+pub struct FakePin {
+    pub levels: VecDeque<bool>,
+    pub level: bool,
+    pub broken: bool,
+    pub polls: usize,
+}
+
+impl FakePin {
+    /// Pin which reads low `low_polls` times, then high
+    pub fn high_after(low_polls: usize) -> Self {
+        FakePin {
+            levels: std::vec![false; low_polls].into(),
+            level: true,
+            ..FakePin::default()
+        }
+    }
+}
+
+impl embedded_hal::digital::ErrorType for FakePin {
+    type Error = FakeError;
+}
+
+impl embedded_hal::digital::InputPin for FakePin {
+    fn is_high(&mut self) -> Result<bool, FakeError> {
+        self.polls += 1;
+        if self.broken {
+            return Err(FakeError);
+        }
+        Ok(self.levels.pop_front().unwrap_or(self.level))
+    }
+
+    fn is_low(&mut self) -> Result<bool, FakeError> {
+        Ok(!self.is_high()?)
+    }
+}
+
+/// Clock advancing by `step` microseconds on every call
+pub struct FakeClock {
+    pub now: u32,
+    pub step: u32,
+}
+
+impl crate::SampleClock for FakeClock {
+    fn now_us(&mut self) -> u32 {
+        self.now = self.now.wrapping_add(self.step);
+        self.now
+    }
+}
+
+/// Logger discarding all `defmt` output
+#[cfg(feature = "defmt")]
+#[defmt::global_logger]
+struct NoLogger;
+
+#[cfg(feature = "defmt")]
+unsafe impl defmt::Logger for NoLogger {
+    fn acquire() {}
+    unsafe fn flush() {}
+    unsafe fn release() {}
+    unsafe fn write(_bytes: &[u8]) {}
+}
@@ -0,0 +1,78 @@
+//! Tests of the blocking I2C driver
+
+use super::fake;
+use crate::blocking::i2c::{I2cAddr, L3gd20};
+use crate::*;
+
+#[test]
+fn new_raw_addr_accepts_only_sensor_addresses() {
+    let (bus, sensor) = fake::i2c(0x6B);
+    assert!(matches!(
+        L3gd20::new_raw_addr(bus, 0xD6),
+        Err(Error::InvalidAddress)
+    ));
+    // Rejected before touching the bus
+    assert!(sensor.borrow().reads.is_empty());
+
+    let (bus, sensor) = fake::i2c(0x6B);
+    assert!(L3gd20::new_raw_addr(bus, 0x6B).is_ok());
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x0F);
+
+    let (bus, _) = fake::i2c(I2cAddr::Sa0Low as u8);
+    assert!(L3gd20::new_raw_addr(bus, 0x6A).is_ok());
+}
+
+#[test]
+fn bus_errors_propagate() {
+    let (bus, sensor) = fake::i2c(0x6B);
+    sensor.borrow_mut().fail_next = 1;
+    assert!(matches!(
+        L3gd20::new(bus, I2cAddr::Sa0High),
+        Err(Error::Bus(fake::FakeError))
+    ));
+
+    let (bus, sensor) = fake::i2c(0x6B);
+    let mut l3gd20 = L3gd20::new(bus, I2cAddr::Sa0High).unwrap();
+    sensor.borrow_mut().set_sample([8, 9, 10]);
+    sensor.borrow_mut().fail_next = 1;
+    assert_eq!(l3gd20.gyro().unwrap_err(), fake::FakeError);
+    let gyro = l3gd20.gyro().unwrap();
+    assert_eq!([gyro.x, gyro.y, gyro.z], [8, 9, 10]);
+    sensor.borrow_mut().fail_next = 1;
+    let gyro = fake::retry(1, || l3gd20.gyro()).unwrap();
+    assert_eq!([gyro.x, gyro.y, gyro.z], [8, 9, 10]);
+    sensor.borrow_mut().broken = true;
+    assert_eq!(
+        l3gd20.read_register(Register::WHO_AM_I).unwrap_err(),
+        fake::FakeError
+    );
+}
+
+/// Generic application code
+fn read_through_trait<G: crate::blocking::gyroscope::Gyroscope>(
+    gyro: &mut G,
+) -> Result<(u8, [i16; 3]), G::Error> {
+    gyro.set_scale(Scale::Dps500)?;
+    Ok((gyro.who_am_i()?, super::xyz(gyro.gyro()?)))
+}
+
+#[test]
+fn gyroscope_trait_covers_both_buses() {
+    let (bus, i2c_sensor) = fake::i2c(0x6B);
+    let mut i2c = L3gd20::new(bus, I2cAddr::Sa0High).unwrap();
+    i2c_sensor.borrow_mut().set_sample([1, 2, 3]);
+    assert_eq!(
+        read_through_trait(&mut i2c).unwrap(),
+        (WHO_AM_I_L3GD20, [1, 2, 3])
+    );
+    assert_eq!(i2c_sensor.borrow().reg(Register::CTRL_REG4), 0x10);
+
+    let (spi, spi_sensor) = fake::spi();
+    let mut spi = crate::blocking::spi::L3gd20::new(spi).unwrap();
+    spi_sensor.borrow_mut().set_sample([4, 5, 6]);
+    assert_eq!(
+        read_through_trait(&mut spi).unwrap(),
+        (WHO_AM_I_L3GD20, [4, 5, 6])
+    );
+    assert_eq!(spi_sensor.borrow().reg(Register::CTRL_REG4), 0x10);
+}
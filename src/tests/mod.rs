@@ -0,0 +1,14 @@
+//! Driver tests against a simulated sensor
+
+mod asynchronous;
+mod fake;
+mod i2c;
+mod spi;
+mod types;
+
+use crate::I16x3;
+
+/// Components of `v` as an array, for comparisons
+fn xyz(v: I16x3) -> [i16; 3] {
+    [v.x, v.y, v.z]
+}
@@ -0,0 +1,1682 @@
+//! Tests of the blocking SPI driver
+
+use super::fake::{self, FakeClock, FakeError, FakePin, FakeSpi, Handle, NoDelay};
+use super::xyz;
+use crate::blocking::spi::{BatchReader, FifoSession, L3gd20};
+use crate::*;
+
+/// Driver connected to a fresh simulated sensor
+fn driver() -> (L3gd20<FakeSpi>, Handle) {
+    let (spi, sensor) = fake::spi();
+    let l3gd20 = L3gd20::new(spi).unwrap();
+    (l3gd20, sensor)
+}
+
+#[test]
+fn verify_scale_compares_mean_rate_with_reference() {
+    let (mut l3gd20, sensor) = driver();
+    // 11428 LSB are 100 dps at the default 250 dps scale
+    sensor.borrow_mut().set_sample([0, 11428, 0]);
+    let mut delay = NoDelay::default();
+
+    assert!(l3gd20
+        .verify_scale(100.0, Axis::Y, 0.5, &mut delay)
+        .unwrap());
+    assert!(!l3gd20
+        .verify_scale(150.0, Axis::Y, 0.5, &mut delay)
+        .unwrap());
+    assert!(!l3gd20
+        .verify_scale(100.0, Axis::X, 0.5, &mut delay)
+        .unwrap());
+    let period_ns = Odr::Hz95.period_us() as u64 * 1000;
+    assert_eq!(
+        delay.total_ns,
+        3 * SCALE_VERIFICATION_SAMPLES as u64 * period_ns
+    );
+}
+
+#[test]
+fn gyro_with_variance_pushes_into_window() {
+    let (mut l3gd20, sensor) = driver();
+    let mut window = VarWindow::<4>::new();
+    for x in [10, 20, 10, 20] {
+        sensor.borrow_mut().push_sample([x, 0, 0]);
+    }
+    for _ in 0..3 {
+        l3gd20.gyro_with_variance(&mut window).unwrap();
+    }
+    let (gyro, var) = l3gd20.gyro_with_variance(&mut window).unwrap();
+    assert_eq!(xyz(gyro), [20, 0, 0]);
+    assert_eq!(window.len(), 4);
+    assert_eq!((var.x, var.y), (25.0, 0.0));
+}
+
+#[test]
+fn measure_startup_time_times_out() {
+    let (mut l3gd20, sensor) = driver();
+    let mut clock = FakeClock { now: 0, step: 100 };
+    let mut drdy = FakePin::high_after(3);
+    sensor.borrow_mut().push_sample([1, 2, 3]);
+    assert_eq!(l3gd20.measure_startup_time(&mut drdy, &mut clock), Ok(400));
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x0F);
+    // The pending sample was discarded
+    assert!(sensor.borrow().queue_is_empty(Register::OUT_X_L));
+    l3gd20.set_axes_enabled(true, false, false).unwrap();
+    let mut drdy = FakePin::high_after(0);
+    assert_eq!(l3gd20.measure_startup_time(&mut drdy, &mut clock), Ok(100));
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x0A);
+
+    let mut drdy = FakePin::default();
+    assert_eq!(
+        l3gd20.measure_startup_time(&mut drdy, &mut clock),
+        Err(Error::Timeout)
+    );
+    assert_eq!(drdy.polls, STARTUP_TIMEOUT_US as usize / 100 + 1);
+
+    drdy.broken = true;
+    assert_eq!(
+        l3gd20.measure_startup_time(&mut drdy, &mut clock),
+        Err(Error::Pin)
+    );
+}
+
+#[test]
+fn single_byte_read_mode_reads_each_register() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([-300, 2, 0x1234]);
+    assert_eq!(l3gd20.read_mode(), ReadMode::Burst);
+
+    let before = sensor.borrow().transactions;
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [-300, 2, 0x1234]);
+    assert_eq!(sensor.borrow().transactions - before, 1);
+
+    l3gd20.set_read_mode(ReadMode::SingleByte);
+    let before = sensor.borrow().transactions;
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [-300, 2, 0x1234]);
+    assert_eq!(sensor.borrow().transactions - before, 6);
+}
+
+#[test]
+fn read_all_registers_decodes_all_blocks() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::REFERENCE, 0x21);
+        sensor.set(Register::FIFO_CTRL_REG, 0x4A);
+        sensor.set(Register::FIFO_SRC_REG, 0x83);
+        sensor.set(Register::INT1_CFG, 0x42);
+        sensor.set(Register::INT1_SRC, 0x42);
+        sensor.set(Register::INT1_DURATION, 0x87);
+        sensor.set_sample([1, -2, 3]);
+    }
+    // Enable the FIFO in Stream mode
+    l3gd20.write_register(Register::CTRL_REG5, 0x40).unwrap();
+    let before = sensor.borrow().transactions;
+    let regs = l3gd20.read_all_registers().unwrap();
+    assert_eq!(sensor.borrow().transactions - before, 3);
+    // Reading REFERENCE would reset the high-pass filter
+    assert!(!sensor.borrow().reads.contains(&(Register::REFERENCE as u8)));
+    assert_eq!(regs.ctrl[0], 0x0F);
+    assert_eq!(regs.reference, None);
+    assert_eq!(xyz(regs.measurements.gyro), [1, -2, 3]);
+    // The FIFO is enabled, but the burst does not wrap around at OUT_Z_H
+    assert_eq!(regs.fifo_mode, FifoMode::Stream);
+    assert_eq!(regs.fifo_watermark, 0x0A);
+    assert!(regs.fifo_status.watermark && regs.fifo_status.stored == 3);
+    assert_eq!(
+        regs.int1_config,
+        Int1Config::default().high(Axis::X).latched(true)
+    );
+    assert!(regs.int1_source.active && regs.int1_source.x_high);
+    assert_eq!((regs.int1_duration, regs.int1_wait), (0x07, true));
+    assert!(l3gd20.cached_status().new_data);
+
+    // Reference mode of the high-pass filter
+    l3gd20.write_register(Register::CTRL_REG2, 0x10).unwrap();
+    let regs = l3gd20.read_all_registers().unwrap();
+    assert_eq!(regs.reference, Some(0x21));
+}
+
+#[test]
+fn auto_power_sleeps_when_idle_and_wakes_on_motion() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([100, -100, 0]);
+    let mut modes = std::vec::Vec::new();
+    for _ in 0..5 {
+        modes.push(l3gd20.auto_power(10.0, 3).unwrap());
+    }
+    use PowerMode::*;
+    assert_eq!(modes, [Normal, Normal, Sleep, Normal, Sleep]);
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1) & 0x0F, 0x08);
+
+    // 10 dps are 1143 LSB at 250 dps
+    l3gd20.auto_power(10.0, 3).unwrap();
+    sensor.borrow_mut().set_sample([0, 1200, 0]);
+    assert_eq!(l3gd20.auto_power(10.0, 3).unwrap(), Normal);
+    assert_eq!(l3gd20.auto_power(10.0, 3).unwrap(), Normal);
+
+    l3gd20.set_power_mode(PowerDown).unwrap();
+    assert_eq!(l3gd20.auto_power(10.0, 3).unwrap(), PowerDown);
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1) & 0x08, 0);
+}
+
+#[test]
+fn bus_error_without_retry_surfaces_once() {
+    let (spi, sensor) = fake::spi();
+    sensor.borrow_mut().fail_next = 1;
+    assert!(matches!(L3gd20::new(spi), Err(Error::Bus(FakeError))));
+
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([5, 6, 7]);
+    sensor.borrow_mut().fail_next = 1;
+    assert_eq!(
+        l3gd20.read_register(Register::WHO_AM_I).unwrap_err(),
+        FakeError
+    );
+    assert_eq!(
+        l3gd20.read_register(Register::WHO_AM_I).unwrap(),
+        WHO_AM_I_L3GD20
+    );
+    sensor.borrow_mut().fail_next = 1;
+    assert_eq!(l3gd20.gyro().unwrap_err(), FakeError);
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [5, 6, 7]);
+    assert_eq!(sensor.borrow().failures, 2);
+}
+
+#[test]
+fn transient_bus_error_is_retried() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([5, 6, 7]);
+    sensor.borrow_mut().fail_next = 1;
+    let transactions = sensor.borrow().transactions;
+    assert_eq!(xyz(fake::retry(1, || l3gd20.gyro()).unwrap()), [5, 6, 7]);
+    assert_eq!(sensor.borrow().failures, 1);
+    assert_eq!(sensor.borrow().transactions, transactions + 2);
+
+    // More consecutive errors than retries
+    sensor.borrow_mut().fail_next = 2;
+    assert_eq!(fake::retry(1, || l3gd20.gyro()).unwrap_err(), FakeError);
+    assert_eq!(xyz(fake::retry(0, || l3gd20.gyro()).unwrap()), [5, 6, 7]);
+}
+
+#[test]
+fn persistent_bus_error_surfaces() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().broken = true;
+    assert_eq!(l3gd20.gyro().unwrap_err(), FakeError);
+    // Every retry fails, the last error is returned
+    assert_eq!(fake::retry(2, || l3gd20.gyro()).unwrap_err(), FakeError);
+    assert_eq!(sensor.borrow().failures, 4);
+    // Only overruns are retried, a bus error is returned right away
+    assert_eq!(l3gd20.gyro_clean(2).unwrap_err(), FakeError);
+    assert_eq!(sensor.borrow().failures, 5);
+
+    sensor.borrow_mut().broken = false;
+    assert!(l3gd20.gyro().is_ok());
+}
+
+#[test]
+fn gyro_clean_retries_overrun() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.queue(Register::STATUS_REG, &[0xFF, 0x0F]);
+        sensor.push_sample([1, 1, 1]);
+        sensor.push_sample([2, 2, 2]);
+    }
+    assert_eq!(xyz(l3gd20.gyro_clean(1).unwrap()), [2, 2, 2]);
+
+    // The last sample is returned if every attempt was overrun
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.queue(Register::STATUS_REG, &[0xFF, 0xFF, 0x0F]);
+        sensor.push_sample([3, 3, 3]);
+        sensor.push_sample([4, 4, 4]);
+    }
+    assert_eq!(xyz(l3gd20.gyro_clean(1).unwrap()), [4, 4, 4]);
+}
+
+#[test]
+fn heading_delta_uses_body_frame_rate() {
+    let (mut l3gd20, sensor) = driver();
+    // 1143 LSB are 10 dps at 250 dps, on the sensor X-axis
+    sensor.borrow_mut().set_sample([1143, 0, 0]);
+    // Body Z is sensor X
+    l3gd20.set_mount_matrix([[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0]]);
+    l3gd20.set_scale_correction([2.0, 1.0, 1.0]);
+
+    let mut heading = 355.0;
+    let delta = l3gd20.heading_delta_deg(0.5, &mut heading).unwrap();
+    assert!((delta - 10.0).abs() < 0.01);
+    assert!((heading - 5.0).abs() < 0.01);
+
+    let angles = l3gd20.gyro_integrate(0.5).unwrap();
+    assert_eq!(angles.z, delta);
+
+    let mut heading = 2.0;
+    sensor.borrow_mut().set_sample([-1143, 0, 0]);
+    l3gd20.heading_delta_deg(0.5, &mut heading).unwrap();
+    assert!((heading - 352.0).abs() < 0.01);
+
+    // A tiny negative delta from 0 stays within [0, 360)
+    l3gd20.set_mount_matrix([[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+    sensor.borrow_mut().set_sample([0, 0, -1]);
+    let mut heading = 0.0;
+    let delta = l3gd20.heading_delta_deg(1e-5, &mut heading).unwrap();
+    assert!(delta < 0.0);
+    assert_eq!(heading, 0.0);
+}
+
+#[test]
+fn status_masking_ignores_disabled_axes() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_axes_enabled(true, true, false).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x0B);
+    // Overrun and new data on Z only
+    sensor.borrow_mut().set(Register::STATUS_REG, 0xCC);
+
+    let status = l3gd20.status().unwrap();
+    assert!(status.overrun && status.new_data);
+    assert!(!l3gd20.all_new().unwrap());
+
+    l3gd20.set_status_masking(true);
+    let status = l3gd20.status().unwrap();
+    assert!(!status.overrun && !status.new_data && !status.z_new);
+    assert!(!l3gd20.cached_status().new_data);
+
+    // New data on X and Y is all that is needed
+    sensor.borrow_mut().set(Register::STATUS_REG, 0x0B);
+    assert!(l3gd20.all_new().unwrap());
+    l3gd20.set_status_masking(false);
+    assert!(!l3gd20.all_new().unwrap());
+}
+
+#[test]
+fn disabling_all_axes_sleeps_and_keeps_axes() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_axes_enabled(true, false, true).unwrap();
+    l3gd20.set_axes_enabled(false, false, false).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x08);
+    assert_eq!(l3gd20.power_mode().unwrap(), PowerMode::Sleep);
+
+    l3gd20.set_power_mode(PowerMode::Normal).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x0E);
+}
+
+#[test]
+fn all_c_reads_temperature_status_and_gyro() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::OUT_TEMP, (-5i8) as u8);
+        sensor.set(Register::STATUS_REG, 0x8F);
+        sensor.set_sample([-1, 256, 7]);
+    }
+    let before = sensor.borrow().transactions;
+    let sample = l3gd20.all_c().unwrap();
+    assert_eq!(sensor.borrow().transactions - before, 1);
+    assert_eq!([sample.x, sample.y, sample.z], [-1, 256, 7]);
+    assert_eq!((sample.temp, sample.status), (-5, 0x8F));
+    assert!(l3gd20.cached_status().overrun);
+}
+
+#[test]
+fn estimated_current_follows_power_mode() {
+    let (mut l3gd20, sensor) = driver();
+    assert_eq!(l3gd20.estimated_current_ua().unwrap(), 6_100);
+    l3gd20.set_power_mode(PowerMode::Sleep).unwrap();
+    assert_eq!(l3gd20.estimated_current_ua().unwrap(), 2_000);
+    l3gd20.set_power_mode(PowerMode::PowerDown).unwrap();
+    assert_eq!(l3gd20.estimated_current_ua().unwrap(), 5);
+
+    // Independent of the output data rate, but not of the model
+    l3gd20.set_power_mode(PowerMode::Normal).unwrap();
+    l3gd20.set_odr(Odr::Hz760).unwrap();
+    assert_eq!(l3gd20.estimated_current_ua().unwrap(), 6_100);
+    sensor
+        .borrow_mut()
+        .set(Register::WHO_AM_I, WHO_AM_I_L3GD20H);
+    assert_eq!(l3gd20.estimated_current_ua().unwrap(), 5_000);
+    l3gd20.set_power_mode(PowerMode::Sleep).unwrap();
+    assert_eq!(l3gd20.estimated_current_ua().unwrap(), 2_500);
+}
+
+#[test]
+fn power_down_and_up_only_toggle_pd() {
+    let (mut l3gd20, sensor) = driver();
+    // 190 Hz, bandwidth 2, X axis only, written behind the driver's back
+    l3gd20
+        .write_register(Register::CTRL_REG1, 0b0110_1001)
+        .unwrap();
+    l3gd20.power_down().unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0b0110_0001);
+    l3gd20.power_up().unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0b0110_1001);
+
+    // Leaving sleep mode enables the axes selected in the driver
+    l3gd20.sleep().unwrap();
+    l3gd20.power_down().unwrap();
+    l3gd20.power_up().unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0b0110_1111);
+}
+
+#[test]
+fn gyro_axes_reads_selected_range_only() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([11, -22, 33]);
+    let reads_of = |sensor: &Handle, before: usize| sensor.borrow().reads[before..].to_vec();
+
+    let before = sensor.borrow().reads.len();
+    assert_eq!(
+        l3gd20.gyro_axes::<{ AXES_X | AXES_Y }>().unwrap(),
+        [11, -22, 0]
+    );
+    assert_eq!(reads_of(&sensor, before), [0x28, 0x29, 0x2A, 0x2B]);
+
+    let before = sensor.borrow().reads.len();
+    assert_eq!(l3gd20.gyro_axes::<AXES_Z>().unwrap(), [0, 0, 33]);
+    assert_eq!(reads_of(&sensor, before), [0x2C, 0x2D]);
+
+    let before = sensor.borrow().reads.len();
+    assert_eq!(
+        l3gd20.gyro_axes::<{ AXES_X | AXES_Z }>().unwrap(),
+        [11, 0, 33]
+    );
+    assert_eq!(reads_of(&sensor, before).len(), 6);
+
+    l3gd20.set_read_mode(ReadMode::SingleByte);
+    let before = sensor.borrow().reads.len();
+    assert_eq!(
+        l3gd20.gyro_axes::<{ AXES_X | AXES_Z }>().unwrap(),
+        [11, 0, 33]
+    );
+    assert_eq!(reads_of(&sensor, before), [0x28, 0x29, 0x2C, 0x2D]);
+    assert_eq!(l3gd20.gyro_axes::<0>().unwrap(), [0, 0, 0]);
+}
+
+#[test]
+fn gyro_axes_is_corrected_like_gyro() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([11, -22, 33]);
+    l3gd20
+        .set_bias(I16x3 { x: 1, y: 2, z: 3 })
+        .set_sign_convention([false, true, true]);
+    let gyro = l3gd20.gyro().unwrap();
+    assert_eq!(xyz(gyro), [10, 24, -30]);
+    assert_eq!(
+        l3gd20.gyro_axes::<{ AXES_X | AXES_Y }>().unwrap(),
+        [10, 24, 0]
+    );
+    assert_eq!(l3gd20.gyro_axes::<AXES_Z>().unwrap(), [0, 0, -30]);
+}
+
+#[test]
+fn clipping_stats_checks_raw_limits() {
+    let (mut l3gd20, sensor) = driver();
+    // The bias must neither hide nor fake clipping
+    l3gd20.set_bias(I16x3 {
+        x: 10,
+        y: -10,
+        z: 1,
+    });
+    l3gd20.set_sign_convention([true, false, false]);
+    for sample in [
+        [i16::MAX, i16::MIN, i16::MIN + 1],
+        [i16::MIN, 0, i16::MAX - 1],
+        [0, i16::MIN, 0],
+    ] {
+        sensor.borrow_mut().push_sample(sample);
+    }
+    let mut delay = NoDelay::default();
+    assert_eq!(l3gd20.clipping_stats(3, &mut delay).unwrap(), [2, 2, 0]);
+}
+
+#[test]
+fn fifo_session_drains_on_watermark() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_bias(I16x3 { x: 1, y: 0, z: 0 });
+    let mut session = FifoSession::new(&mut l3gd20, 40).unwrap();
+    {
+        let sensor = sensor.borrow();
+        assert_eq!(sensor.reg(Register::FIFO_CTRL_REG), 0x40 | 31);
+        assert_ne!(sensor.reg(Register::CTRL_REG5) & 0x40, 0);
+    }
+
+    let mut out = [I16x3 { x: 0, y: 0, z: 0 }; 2];
+    sensor.borrow_mut().set(Register::FIFO_SRC_REG, 3);
+    assert_eq!(session.poll(&mut out).unwrap(), 0);
+
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::FIFO_SRC_REG, 0x80 | 3);
+        for x in [10, 20, 30] {
+            sensor.push_sample([x, -x, 0]);
+        }
+    }
+    assert_eq!(session.poll(&mut out).unwrap(), 2);
+    assert_eq!([xyz(out[0]), xyz(out[1])], [[9, -10, 0], [19, -20, 0]]);
+    assert!(!sensor.borrow().queue_is_empty(Register::OUT_X_L));
+
+    session.close().unwrap();
+    let sensor = sensor.borrow();
+    assert_eq!(sensor.reg(Register::FIFO_CTRL_REG) & 0xE0, 0);
+    assert_eq!(sensor.reg(Register::CTRL_REG5) & 0x40, 0);
+}
+
+#[test]
+fn all_detailed_decodes_status_from_burst() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::OUT_TEMP, 3);
+        sensor.set(Register::STATUS_REG, 0x98);
+        sensor.set_sample([100, 200, 300]);
+    }
+    let before = sensor.borrow().transactions;
+    let (measurements, status) = l3gd20.all_detailed().unwrap();
+    assert_eq!(sensor.borrow().transactions - before, 1);
+    assert_eq!(xyz(measurements.gyro), [100, 200, 300]);
+    assert_eq!(measurements.temp_raw, 3);
+    assert!(status.overrun && status.x_overrun && !status.y_overrun);
+    assert!(status.new_data && !status.x_new);
+    assert!(l3gd20.cached_status().x_overrun);
+    assert_eq!(xyz(l3gd20.all().unwrap().gyro), [100, 200, 300]);
+}
+
+#[test]
+fn force_interrupt_test_restores_configuration() {
+    let (mut l3gd20, sensor) = driver();
+    let configure = |sensor: &Handle| {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::CTRL_REG3, 0x08);
+        sensor.set(Register::INT1_CFG, 0x12);
+        sensor.set(Register::INT1_TSH_XL, 0x34);
+        sensor.set(Register::INT1_DURATION, 0x05);
+    };
+    let assert_restored = |sensor: &Handle| {
+        let sensor = sensor.borrow();
+        assert_eq!(sensor.reg(Register::CTRL_REG3), 0x08);
+        assert_eq!(sensor.reg(Register::INT1_CFG), 0x12);
+        assert_eq!(sensor.reg(Register::INT1_TSH_XL), 0x34);
+        assert_eq!(sensor.reg(Register::INT1_DURATION), 0x05);
+    };
+
+    configure(&sensor);
+    let mut pin = FakePin::high_after(1);
+    assert_eq!(l3gd20.force_interrupt_test(&mut pin), Ok(true));
+    assert_eq!(sensor.borrow().last_write(Register::INT1_CFG), Some(0x12));
+    assert_restored(&sensor);
+
+    // No interrupt within two samples
+    let mut pin = FakePin::default();
+    assert_eq!(l3gd20.force_interrupt_test(&mut pin), Ok(false));
+    assert_restored(&sensor);
+
+    // No samples at all
+    sensor.borrow_mut().set(Register::STATUS_REG, 0);
+    assert_eq!(l3gd20.force_interrupt_test(&mut pin), Err(Error::Timeout));
+    assert_restored(&sensor);
+
+    pin.broken = true;
+    assert_eq!(l3gd20.force_interrupt_test(&mut pin), Err(Error::Pin));
+    assert_restored(&sensor);
+
+    // Active low polarity
+    sensor.borrow_mut().set(Register::CTRL_REG3, 0x20);
+    let mut pin = FakePin::default();
+    assert_eq!(l3gd20.force_interrupt_test(&mut pin), Ok(true));
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG3), 0x20);
+}
+
+#[test]
+fn gyro_integrate_into_feeds_integrator() {
+    /// Records the accumulated rates
+    #[derive(Default)]
+    struct Recorder(std::vec::Vec<(F32x3, f32)>);
+
+    impl Integrator for Recorder {
+        fn accumulate(&mut self, rate: F32x3, dt: f32) {
+            self.0.push((rate, dt));
+        }
+    }
+
+    let (mut l3gd20, sensor) = driver();
+    // 2000 dps, 0.07 dps/LSB
+    l3gd20.set_scale(Scale::Dps2000).unwrap();
+    sensor.borrow_mut().set_sample([100, -200, 0]);
+    let mut recorder = Recorder::default();
+    l3gd20.gyro_integrate_into(&mut recorder, 0.01).unwrap();
+    let (rate, dt) = recorder.0[0];
+    assert!((rate.x - 7.0).abs() < 1e-4 && (rate.y + 14.0).abs() < 1e-4);
+    assert_eq!((rate.z, dt), (0.0, 0.01));
+
+    let mut angles = F32x3::default();
+    l3gd20.gyro_integrate_into(&mut angles, 0.5).unwrap();
+    l3gd20.gyro_integrate_into(&mut angles, 0.5).unwrap();
+    assert!((angles.x - 7.0).abs() < 1e-4 && (angles.y + 14.0).abs() < 1e-4);
+}
+
+#[test]
+fn gyro_with_jerk_differentiates_rate() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([i16::MAX, i16::MIN, 100]);
+    let mut prev = I16x3 {
+        x: i16::MIN,
+        y: i16::MAX,
+        z: 100,
+    };
+    let (gyro, jerk) = l3gd20.gyro_with_jerk(&mut prev, 0.1).unwrap();
+    assert_eq!(xyz(gyro), xyz(prev));
+    // No overflow over the full range, 65535 LSB * 0.00875 dps / 0.1 s
+    assert!((jerk.x - 5734.3125).abs() < 0.01);
+    assert!((jerk.y + 5734.3125).abs() < 0.01);
+    assert_eq!(jerk.z, 0.0);
+}
+
+#[test]
+fn apply_setting_parses_key_value_pairs() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.apply_setting("odr", "380").unwrap();
+    l3gd20.apply_setting("bandwidth", "maximum").unwrap();
+    l3gd20.apply_setting("scale", "2000").unwrap();
+    l3gd20.apply_setting("bdu", "1").unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0xBF);
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG4), 0xB0);
+    l3gd20.apply_setting("bdu", "false").unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG4), 0x30);
+
+    let writes = sensor.borrow().writes.len();
+    assert_eq!(l3gd20.apply_setting("odr", "100"), Err(Error::BadValue));
+    assert_eq!(l3gd20.apply_setting("bdu", "yes"), Err(Error::BadValue));
+    assert_eq!(
+        l3gd20.apply_setting("rate", "95"),
+        Err(Error::UnknownSetting)
+    );
+    assert_eq!(sensor.borrow().writes.len(), writes);
+}
+
+#[test]
+fn gyro_deadband_zeroes_small_rates() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([4, -5, i16::MIN]);
+    assert_eq!(xyz(l3gd20.gyro_deadband(5).unwrap()), [0, -5, i16::MIN]);
+    assert_eq!(xyz(l3gd20.gyro_deadband(-5).unwrap()), [0, -5, i16::MIN]);
+    assert_eq!(xyz(l3gd20.gyro_deadband(0).unwrap()), [4, -5, i16::MIN]);
+    assert_eq!(
+        xyz(l3gd20.gyro_deadband(i16::MIN).unwrap()),
+        [0, 0, i16::MIN]
+    );
+}
+
+#[test]
+fn with_saved_state_restores_after_error() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set(Register::INT1_CFG, 0x0A);
+    let result: Result<(), FakeError> = l3gd20.with_saved_state(|l3gd20| {
+        l3gd20.set_scale(Scale::Dps2000)?;
+        l3gd20.write_register(Register::INT1_CFG, 0x00)?;
+        l3gd20.set_power_mode(PowerMode::PowerDown)?;
+        Err(FakeError)
+    });
+    assert_eq!(result, Err(FakeError));
+    let sensor = sensor.borrow();
+    assert_eq!(sensor.reg(Register::CTRL_REG4), 0x00);
+    assert_eq!(sensor.reg(Register::INT1_CFG), 0x0A);
+    assert_eq!(sensor.reg(Register::CTRL_REG1), 0x0F);
+}
+
+#[test]
+fn save_state_skips_reference_in_normal_reset_mode() {
+    let (mut l3gd20, sensor) = driver();
+    let reference = Register::REFERENCE as u8;
+    sensor.borrow_mut().set(Register::REFERENCE, 0x42);
+    let state = l3gd20.save_state().unwrap();
+    l3gd20.write_register(Register::REFERENCE, 0x10).unwrap();
+    l3gd20.restore_state(&state).unwrap();
+    {
+        let sensor = sensor.borrow();
+        assert!(!sensor.reads.contains(&reference));
+        assert_eq!(sensor.reg(Register::REFERENCE), 0x10);
+    }
+
+    l3gd20.write_register(Register::CTRL_REG2, 0x10).unwrap();
+    let state = l3gd20.save_state().unwrap();
+    l3gd20.write_register(Register::REFERENCE, 0x20).unwrap();
+    l3gd20.restore_state(&state).unwrap();
+    let sensor = sensor.borrow();
+    assert!(sensor.reads.contains(&reference));
+    assert_eq!(sensor.reg(Register::REFERENCE), 0x10);
+}
+
+#[test]
+fn gyro_scaled_to_is_independent_of_scale() {
+    let (mut l3gd20, sensor) = driver();
+    // 10 dps at 250 and 2000 dps
+    sensor.borrow_mut().set_sample([1143, -1143, 0]);
+    assert_eq!(l3gd20.gyro_scaled_to(16).unwrap(), (160, -160, 0));
+    l3gd20.set_scale(Scale::Dps2000).unwrap();
+    sensor.borrow_mut().set_sample([143, -143, 0]);
+    assert_eq!(l3gd20.gyro_scaled_to(16).unwrap(), (160, -160, 0));
+}
+
+#[test]
+fn fifo_is_empty_reads_empty_flag() {
+    let (mut l3gd20, sensor) = driver();
+    assert!(l3gd20.fifo_is_empty().unwrap());
+    sensor.borrow_mut().set(Register::FIFO_SRC_REG, 0xC0 | 0x1F);
+    assert!(!l3gd20.fifo_is_empty().unwrap());
+
+    // Drain until the flag is set
+    sensor
+        .borrow_mut()
+        .queue(Register::FIFO_SRC_REG, &[0x02, 0x01, 0x20]);
+    let mut drained = 0;
+    while !l3gd20.fifo_is_empty().unwrap() {
+        l3gd20.gyro().unwrap();
+        drained += 1;
+    }
+    assert_eq!(drained, 2);
+}
+
+#[test]
+fn set_fifo_watermark_fraction_keeps_mode() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20
+        .write_register(Register::FIFO_CTRL_REG, 0x40 | 0x1F)
+        .unwrap();
+    for (fraction, level) in [
+        (0.75, 23),
+        (0.0, 0),
+        (1.0, 31),
+        (-1.0, 0),
+        (2.0, 31),
+        (0.5, 16),
+    ] {
+        l3gd20.set_fifo_watermark_fraction(fraction).unwrap();
+        assert_eq!(sensor.borrow().reg(Register::FIFO_CTRL_REG), 0x40 | level);
+    }
+}
+
+#[test]
+fn sign_convention_negates_selected_axes() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([10, i16::MIN, -30]);
+    l3gd20.set_sign_convention([true, true, false]);
+    assert_eq!(l3gd20.sign_convention(), [true, true, false]);
+    // i16::MIN saturates instead of overflowing
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [-10, i16::MAX, -30]);
+    assert_eq!(xyz(l3gd20.all().unwrap().gyro), [-10, i16::MAX, -30]);
+    l3gd20.set_sign_convention([false; 3]);
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [10, i16::MIN, -30]);
+}
+
+#[test]
+fn measure_read_latency_times_one_read() {
+    let (mut l3gd20, sensor) = driver();
+    let mut clock = FakeClock {
+        now: u32::MAX - 10,
+        step: 25,
+    };
+    let before = sensor.borrow().transactions;
+    // Wraps around during the measurement
+    assert_eq!(l3gd20.measure_read_latency(&mut clock).unwrap(), 25);
+    assert_eq!(sensor.borrow().transactions - before, 1);
+}
+
+#[test]
+fn drain_with_limit_signals_backpressure() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.write_register(Register::CTRL_REG5, 0x40).unwrap();
+    l3gd20
+        .write_register(Register::FIFO_CTRL_REG, 0x40)
+        .unwrap();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.queue(Register::FIFO_SRC_REG, &[5, 2, 0x20]);
+        for x in 1..=5 {
+            sensor.push_sample([x, 0, 0]);
+        }
+    }
+    let mut out = [I16x3 { x: 0, y: 0, z: 0 }; 4];
+    assert_eq!(l3gd20.drain_with_limit(&mut out, 3).unwrap(), (3, true));
+    assert_eq!(
+        out[..3].iter().map(|s| s.x).collect::<std::vec::Vec<_>>(),
+        [1, 2, 3]
+    );
+    assert_eq!(l3gd20.drain_with_limit(&mut out, 3).unwrap(), (2, false));
+    assert_eq!([out[0].x, out[1].x], [4, 5]);
+    // Empty FIFO
+    assert_eq!(l3gd20.drain_with_limit(&mut out, 3).unwrap(), (0, false));
+
+    sensor.borrow_mut().set(Register::FIFO_SRC_REG, 8);
+    assert_eq!(
+        l3gd20.drain_with_limit(&mut out[..1], 3).unwrap(),
+        (1, true)
+    );
+}
+
+#[test]
+fn capabilities_depend_on_detected_model() {
+    let (mut l3gd20, sensor) = driver();
+    let caps = l3gd20.capabilities().unwrap();
+    assert_eq!(caps.model, Model::L3gd20);
+    assert_eq!(caps.odrs_hz, [95.0, 190.0, 380.0, 760.0]);
+    assert_eq!((caps.fifo_depth, caps.low_odr), (32, false));
+
+    sensor
+        .borrow_mut()
+        .set(Register::WHO_AM_I, WHO_AM_I_L3GD20H);
+    let caps = l3gd20.capabilities().unwrap();
+    assert_eq!(caps.model, Model::L3gd20h);
+    assert_eq!(caps.odrs_hz.len(), 7);
+    assert!(caps.low_odr);
+
+    sensor.borrow_mut().set(Register::WHO_AM_I, 0x00);
+    assert!(matches!(
+        l3gd20.capabilities(),
+        Err(Error::InvalidWhoAmI(0x00))
+    ));
+}
+
+#[test]
+fn cached_status_is_refreshed_by_status_reads() {
+    let (mut l3gd20, sensor) = driver();
+    assert!(!l3gd20.cached_status().new_data);
+
+    sensor.borrow_mut().set(Register::STATUS_REG, 0x88);
+    l3gd20.all().unwrap();
+    let cached = l3gd20.cached_status();
+    assert!(cached.overrun && cached.new_data);
+
+    // Reads without STATUS_REG keep the cached value
+    sensor.borrow_mut().set(Register::STATUS_REG, 0x00);
+    let transactions = sensor.borrow().transactions;
+    l3gd20.gyro().unwrap();
+    assert!(l3gd20.cached_status().overrun);
+    assert_eq!(sensor.borrow().transactions - transactions, 1);
+
+    l3gd20.status().unwrap();
+    assert!(!l3gd20.cached_status().overrun);
+}
+
+#[test]
+fn configure_precision_selects_low_noise_settings() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_odr(Odr::Hz760).unwrap();
+    l3gd20.set_bandwidth(Bandwidth::Maximum).unwrap();
+    l3gd20.set_scale(Scale::Dps2000).unwrap();
+    l3gd20.configure_precision().unwrap();
+    let sensor = sensor.borrow();
+    // 95 Hz, lowest bandwidth, powered up with all axes
+    assert_eq!(sensor.reg(Register::CTRL_REG1), 0x0F);
+    // BDU, 250 dps
+    assert_eq!(sensor.reg(Register::CTRL_REG4), 0x80);
+    assert_eq!(sensor.write_count(Register::LOW_ODR), 0);
+}
+
+#[test]
+fn configure_precision_uses_low_odr_on_l3gd20h() {
+    let (mut l3gd20, sensor) = driver();
+    sensor
+        .borrow_mut()
+        .set(Register::WHO_AM_I, WHO_AM_I_L3GD20H);
+    l3gd20.configure_precision().unwrap();
+    assert_eq!(l3gd20.low_odr().unwrap(), Some(LowOdr::Hz12_5));
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x0F);
+
+    l3gd20.configure_high_rate().unwrap();
+    assert_eq!(l3gd20.low_odr().unwrap(), None);
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0xFF);
+}
+
+#[test]
+fn configure_high_rate_selects_fast_settings() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.configure_high_rate().unwrap();
+    let sensor = sensor.borrow();
+    // 760 Hz, maximum bandwidth
+    assert_eq!(sensor.reg(Register::CTRL_REG1), 0xFF);
+    // BDU, 2000 dps
+    assert_eq!(sensor.reg(Register::CTRL_REG4), 0xB0);
+    // Stream mode
+    assert_eq!(sensor.reg(Register::FIFO_CTRL_REG) >> 5, 0b010);
+    assert_ne!(sensor.reg(Register::CTRL_REG5) & 0x40, 0);
+}
+
+#[test]
+fn gyro_delta_encoded_flags_overflow() {
+    let (mut l3gd20, sensor) = driver();
+    let mut prev = I16x3 {
+        x: 100,
+        y: 100,
+        z: i16::MIN,
+    };
+    sensor.borrow_mut().set_sample([227, -28, i16::MAX]);
+    assert_eq!(
+        l3gd20.gyro_delta_encoded(&mut prev).unwrap(),
+        [127, DELTA_OVERFLOW, DELTA_OVERFLOW]
+    );
+    assert_eq!(xyz(prev), [227, -28, i16::MAX]);
+
+    sensor.borrow_mut().set_sample([100, -156, i16::MAX]);
+    assert_eq!(
+        l3gd20.gyro_delta_encoded(&mut prev).unwrap(),
+        [-127, DELTA_OVERFLOW, 0]
+    );
+}
+
+#[test]
+fn dominant_axis_needs_threshold() {
+    let (mut l3gd20, sensor) = driver();
+    // 5 dps is about 571 LSB at 250 dps full scale
+    sensor.borrow_mut().set_sample([500, -560, 100]);
+    assert_eq!(l3gd20.dominant_axis().unwrap(), None);
+
+    sensor.borrow_mut().set_sample([500, -600, 100]);
+    assert_eq!(l3gd20.dominant_axis().unwrap(), Some(Axis::Y));
+
+    // Ties go to the first axis
+    sensor.borrow_mut().set_sample([700, 100, -700]);
+    assert_eq!(l3gd20.dominant_axis().unwrap(), Some(Axis::X));
+}
+
+#[test]
+fn update_bias_if_idle_tracks_at_rest() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([64, -64, 10]);
+    assert!(l3gd20.update_bias_if_idle(100).unwrap());
+    // Moved by 1/64 of the difference
+    assert_eq!(xyz(l3gd20.bias()), [1, -1, 0]);
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [63, -63, 10]);
+
+    sensor.borrow_mut().set_sample([1000, 0, 0]);
+    assert!(!l3gd20.update_bias_if_idle(100).unwrap());
+    assert_eq!(xyz(l3gd20.bias()), [1, -1, 0]);
+}
+
+#[test]
+fn expect_who_am_i_reports_found_id() {
+    let (mut l3gd20, sensor) = driver();
+    assert!(l3gd20.expect_who_am_i(WHO_AM_I_L3GD20).is_ok());
+    sensor.borrow_mut().set(Register::WHO_AM_I, 0x42);
+    assert!(matches!(
+        l3gd20.expect_who_am_i(WHO_AM_I_L3GD20),
+        Err(Error::WrongDevice(0x42))
+    ));
+}
+
+#[cfg(feature = "defmt")]
+#[test]
+fn fifo_log_drains_fifo() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.queue(Register::FIFO_SRC_REG, &[0x02, 0x20]);
+        sensor.push_sample([1, 2, 3]);
+        sensor.push_sample([4, 5, 6]);
+    }
+    assert_eq!(l3gd20.fifo_log().unwrap(), 2);
+}
+
+#[test]
+fn gyro_settled_waits_after_config_change() {
+    let (mut l3gd20, _sensor) = driver();
+    let mut delay = NoDelay::default();
+    // 5 periods at 95 Hz
+    let settling_ns = 5 * 10_526 * 1000;
+    l3gd20.gyro_settled(&mut delay).unwrap();
+    assert_eq!(delay.total_ns, settling_ns);
+    l3gd20.gyro_settled(&mut delay).unwrap();
+    assert_eq!(delay.total_ns, settling_ns);
+
+    l3gd20.write_register(Register::CTRL_REG2, 0x00).unwrap();
+    l3gd20.gyro_settled(&mut delay).unwrap();
+    assert_eq!(delay.total_ns, 2 * settling_ns);
+
+    // Reads in between do not skip the wait
+    l3gd20.set_bandwidth(Bandwidth::High).unwrap();
+    l3gd20.gyro().unwrap();
+    l3gd20.all().unwrap();
+    l3gd20.gyro_settled(&mut delay).unwrap();
+    assert_eq!(delay.total_ns, 3 * settling_ns);
+}
+
+#[test]
+fn gyro_allan_update_feeds_accumulator() {
+    let (mut l3gd20, sensor) = driver();
+    let mut acc = AllanAccumulator::<1>::new();
+    for x in [10, 14] {
+        sensor.borrow_mut().push_sample([x, 0, 0]);
+        l3gd20.gyro_allan_update(&mut acc).unwrap();
+    }
+    assert_eq!(acc.variance(1).unwrap().x, 8.0);
+}
+
+#[test]
+fn set_int1_duration_ms_keeps_wait_bit() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set(Register::INT1_DURATION, 0x85);
+    // 100 ms is 9.5 periods at 95 Hz
+    l3gd20.set_int1_duration_ms(100).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::INT1_DURATION), 0x8A);
+    l3gd20.set_int1_duration_ms(5000).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::INT1_DURATION), 0xFF);
+}
+
+#[test]
+fn gyro_for_complementary_integrates_rate() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([1000, -2000, 0]);
+    let input = l3gd20.gyro_for_complementary(0.5).unwrap();
+    assert_eq!(input.dt_s, 0.5);
+    assert!((input.rate_dps.x - 8.75).abs() < 1e-4);
+    assert!((input.rate_dps.y + 17.5).abs() < 1e-4);
+    assert!((input.angle_delta_deg.x - 4.375).abs() < 1e-4);
+    assert!((input.angle_delta_deg.y + 8.75).abs() < 1e-4);
+    assert_eq!(input.angle_delta_deg.z, 0.0);
+}
+
+#[test]
+fn detect_bus_fault_on_stuck_lines() {
+    let (mut l3gd20, sensor) = driver();
+    assert!(!l3gd20.detect_bus_fault().unwrap());
+
+    sensor.borrow_mut().regs = [0xFF; 0x40];
+    assert!(l3gd20.detect_bus_fault().unwrap());
+
+    // A single differing byte means the bus works
+    sensor.borrow_mut().set(Register::OUT_Z_H, 0x12);
+    assert!(!l3gd20.detect_bus_fault().unwrap());
+}
+
+#[test]
+fn config_axes_map_to_enable_bits() {
+    let config = Config {
+        axes: AXES_X | AXES_Z,
+        ..Config::default()
+    };
+    let (spi, sensor) = fake::spi();
+    let (mut l3gd20, applied) = L3gd20::new_with_config(spi, config).unwrap();
+    assert_eq!(applied.axes, AXES_X | AXES_Z);
+    // Xen is bit 1, Yen bit 0 and Zen bit 2
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1) & 0x07, 0b110);
+    // Also kept for waking up
+    l3gd20.set_power_mode(PowerMode::PowerDown).unwrap();
+    l3gd20.set_power_mode(PowerMode::Normal).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1) & 0x07, 0b110);
+}
+
+#[test]
+fn gyro_with_endian_ignores_ble() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([0x0102, -2, 0x7F00]);
+    assert_eq!(
+        xyz(l3gd20.gyro_with_endian(false).unwrap()),
+        [0x0102, -2, 0x7F00]
+    );
+    assert_eq!(
+        xyz(l3gd20.gyro_with_endian(true).unwrap()),
+        [0x0201, -257, 0x007F]
+    );
+}
+
+#[test]
+fn verify_hp_filter_checks_convergence() {
+    let (mut l3gd20, sensor) = driver();
+    let mut delay = NoDelay::default();
+    assert!(!l3gd20.verify_hp_filter(&mut delay).unwrap());
+    assert_eq!(delay.total_ns, 0);
+
+    // HPEN with the high-pass filtered output selected
+    sensor.borrow_mut().set(Register::CTRL_REG5, 0x12);
+    sensor.borrow_mut().set_sample([1000, 1000, 1000]);
+    assert!(!l3gd20.verify_hp_filter(&mut delay).unwrap());
+
+    {
+        let mut sensor = sensor.borrow_mut();
+        for _ in 0..HP_FILTER_VERIFICATION_SAMPLES {
+            sensor.push_sample([1000, -1000, 10]);
+        }
+        sensor.push_sample([400, -20, 10]);
+    }
+    assert!(l3gd20.verify_hp_filter(&mut delay).unwrap());
+}
+
+#[test]
+fn batch_reader_routes_interrupt_after_fifo_setup() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().fail_next = 1;
+    assert!(BatchReader::<_, _, 8>::new(&mut l3gd20, FakePin::default(), 4).is_err());
+    assert_eq!(sensor.borrow().write_count(Register::CTRL_REG3), 0);
+
+    let reader = BatchReader::<_, _, 8>::new(&mut l3gd20, FakePin::default(), 4).unwrap();
+    {
+        let sensor = sensor.borrow();
+        // I2_WTM is set last
+        assert_eq!(
+            sensor.writes.last(),
+            Some(&(Register::CTRL_REG3 as u8, 0x04))
+        );
+        assert_eq!(sensor.reg(Register::FIFO_CTRL_REG), 0x44);
+        assert_ne!(sensor.reg(Register::CTRL_REG5) & 0x40, 0);
+    }
+    reader.close().unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG3), 0);
+}
+
+#[test]
+fn gyro_integrate_accumulates_until_reset() {
+    let (mut l3gd20, sensor) = driver();
+    // 8.75 dps at 250 dps full scale
+    sensor.borrow_mut().set_sample([1000, 0, -2000]);
+    l3gd20.gyro_integrate(0.5).unwrap();
+    let angles = l3gd20.gyro_integrate(0.5).unwrap();
+    assert!((angles.x - 8.75).abs() < 1e-3);
+    assert!((angles.z + 17.5).abs() < 1e-3);
+    assert_eq!(l3gd20.integrated_angles().x, angles.x);
+
+    l3gd20.reset_integration();
+    assert_eq!(l3gd20.integrated_angles().x, 0.0);
+    assert_eq!(l3gd20.integrated_angles().z, 0.0);
+}
+
+#[test]
+fn set_fifo_mode_toggles_fifo_enable() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set(Register::FIFO_CTRL_REG, 0x0A);
+    l3gd20.set_fifo_mode(FifoMode::StreamToFifo).unwrap();
+    {
+        let sensor = sensor.borrow();
+        // Watermark kept
+        assert_eq!(sensor.reg(Register::FIFO_CTRL_REG), 0x6A);
+        assert_eq!(sensor.reg(Register::CTRL_REG5), 0x40);
+    }
+    assert_eq!(l3gd20.fifo_mode().unwrap(), FifoMode::StreamToFifo);
+
+    l3gd20.set_fifo_mode(FifoMode::Bypass).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::FIFO_CTRL_REG), 0x0A);
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG5), 0x00);
+    assert_eq!(l3gd20.fifo_mode().unwrap(), FifoMode::Bypass);
+
+    // Modes of the L3GD20H written behind the driver's back
+    l3gd20
+        .write_register(Register::FIFO_CTRL_REG, 0b1100_0000)
+        .unwrap();
+    assert_eq!(l3gd20.fifo_mode().unwrap(), FifoMode::DynamicStream);
+    l3gd20
+        .write_register(Register::FIFO_CTRL_REG, 0b1110_0000)
+        .unwrap();
+    assert_eq!(l3gd20.fifo_mode().unwrap(), FifoMode::BypassToFifo);
+}
+
+#[test]
+fn gyro_thermal_tracked_follows_temperature_ramp() {
+    let (mut l3gd20, sensor) = driver();
+    // 100 LSB of bias at 25 degrees, 10 LSB/degree on X and -5 LSB/degree on Z
+    let dps = |lsb: f32| lsb * 0.00875;
+    l3gd20.set_thermal_model(ThermalModel {
+        reference_celcius: 25.0,
+        bias_dps: F32x3 {
+            x: dps(100.0),
+            y: 0.0,
+            z: dps(-40.0),
+        },
+        slope_dps_per_celcius: F32x3 {
+            x: dps(10.0),
+            y: 0.0,
+            z: dps(-5.0),
+        },
+    });
+    // Warming up from 25 to 31 degrees, the bias follows the model
+    for delta in [0i16, 2, 4, 6] {
+        {
+            let mut sensor = sensor.borrow_mut();
+            sensor.set(Register::OUT_TEMP, (-delta) as i8 as u8);
+            sensor.set_sample([100 + 10 * delta, 50, -40 - 5 * delta]);
+        }
+        let rate = l3gd20.gyro_thermal_tracked().unwrap();
+        assert!(rate.x.abs() < 1e-4);
+        assert!((rate.y - dps(50.0)).abs() < 1e-4);
+        assert!(rate.z.abs() < 1e-4);
+    }
+}
+
+#[test]
+fn calibrate_thermal_reference_keeps_fractional_temperature() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        // Alternating between 25 and 26 degrees
+        let temps: std::vec::Vec<u8> = (0..THERMAL_CALIBRATION_SAMPLES)
+            .map(|i| if i % 2 == 0 { 0 } else { (-1i8) as u8 })
+            .collect();
+        sensor.queue(Register::OUT_TEMP, &temps);
+        sensor.set_sample([200, 0, -200]);
+    }
+    let before = sensor.borrow().transactions;
+    let model = l3gd20
+        .calibrate_thermal_reference(&mut NoDelay::default())
+        .unwrap();
+    // One burst per sample, the scale is not read again
+    assert_eq!(
+        sensor.borrow().transactions - before,
+        THERMAL_CALIBRATION_SAMPLES as usize + 1
+    );
+    assert_eq!(model.reference_celcius, 25.5);
+    assert!((model.bias_dps.x - 1.75).abs() < 1e-4);
+    assert!((model.bias_dps.z + 1.75).abs() < 1e-4);
+}
+
+#[test]
+fn transfer_passes_command_through() {
+    let (mut l3gd20, sensor) = driver();
+    // Read WHO_AM_I
+    let mut buf = [0x8F, 0x00];
+    l3gd20.transfer(&mut buf).unwrap();
+    assert_eq!(buf[1], WHO_AM_I_L3GD20);
+
+    // Write REFERENCE, bypassing the driver
+    let writes = sensor.borrow().writes.len();
+    l3gd20.transfer(&mut [0x25, 0x5A]).unwrap();
+    let sensor = sensor.borrow();
+    assert_eq!(sensor.writes[writes..], [(Register::REFERENCE as u8, 0x5A)]);
+}
+
+#[test]
+fn temp_profile_reads_one_sample_per_period() {
+    let (mut l3gd20, sensor) = driver();
+    sensor
+        .borrow_mut()
+        .queue(Register::OUT_TEMP, &[25, 24, 23, 22]);
+    let mut delay = NoDelay::default();
+    let mut out = [0i8; 3];
+    assert_eq!(l3gd20.temp_profile(5, &mut delay, &mut out).unwrap(), 3);
+    assert_eq!(out, [25, 24, 23]);
+    // One period at 95 Hz per reading
+    assert_eq!(delay.total_ns, 3 * 10_526 * 1000);
+
+    assert_eq!(l3gd20.temp_profile(1, &mut delay, &mut out).unwrap(), 1);
+    assert_eq!(out[0], 22);
+}
+
+#[test]
+fn resolves_rate_depends_on_scale() {
+    let (mut l3gd20, _sensor) = driver();
+    // Half an LSB is about 4.4 mdps at 250 dps full scale
+    assert!(l3gd20.resolves_rate(0.01).unwrap());
+    assert!(!l3gd20.resolves_rate(0.003).unwrap());
+
+    l3gd20.set_scale(Scale::Dps2000).unwrap();
+    assert!(!l3gd20.resolves_rate(0.01).unwrap());
+    assert!(l3gd20.resolves_rate(-0.05).unwrap());
+}
+
+#[test]
+fn config_descriptor_round_trip() {
+    let (mut source, source_sensor) = driver();
+    {
+        let mut sensor = source_sensor.borrow_mut();
+        for (reg, value) in [
+            (Register::CTRL_REG1, 0x5B),
+            (Register::CTRL_REG2, 0x24),
+            (Register::CTRL_REG3, 0x08),
+            (Register::CTRL_REG4, 0x50),
+            (Register::CTRL_REG5, 0x13),
+        ] {
+            sensor.set(reg, value);
+        }
+    }
+    let descriptor = source.config_descriptor().unwrap();
+    assert_eq!(descriptor, [0x5B, 0x24, 0x08, 0x50, 0x13]);
+
+    let (mut target, target_sensor) = driver();
+    let transactions = target_sensor.borrow().transactions;
+    target.apply_descriptor(&descriptor).unwrap();
+    assert_eq!(target_sensor.borrow().transactions, transactions + 1);
+    assert_eq!(target.config_descriptor().unwrap(), descriptor);
+    // The driver state follows the descriptor
+    target_sensor.borrow_mut().push_sample([0x0102, 0, 0]);
+    assert_eq!(target.gyro().unwrap().x, 0x0102);
+    // 17.5 mdps/LSB at 500 dps full scale
+    target_sensor.borrow_mut().push_sample([0x0102, 0, 0]);
+    assert_eq!(target.gyro_scaled_to(1000).unwrap().0, 4515);
+    target.set_power_mode(PowerMode::PowerDown).unwrap();
+    target.set_power_mode(PowerMode::Normal).unwrap();
+    assert_eq!(
+        target_sensor.borrow().reg(Register::CTRL_REG1) & 0x07,
+        0b011
+    );
+}
+
+#[test]
+fn gyro_clean_without_retries_reads_once() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.queue(Register::STATUS_REG, &[0xFF]);
+        sensor.push_sample([1, 1, 1]);
+        sensor.push_sample([2, 2, 2]);
+    }
+    let transactions = sensor.borrow().transactions;
+    assert_eq!(xyz(l3gd20.gyro_clean(0).unwrap()), [1, 1, 1]);
+    assert_eq!(sensor.borrow().transactions, transactions + 1);
+}
+
+#[test]
+fn gyro_guaranteed_fresh_discards_pending_sample() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.push_sample([1, 1, 1]);
+        sensor.push_sample([2, 2, 2]);
+    }
+    let mut drdy = FakePin::high_after(3);
+    assert_eq!(
+        xyz(l3gd20.gyro_guaranteed_fresh(&mut drdy).unwrap()),
+        [2, 2, 2]
+    );
+    assert_eq!(drdy.polls, 5);
+}
+
+#[test]
+fn gyro_guaranteed_fresh_times_out() {
+    let (mut l3gd20, _sensor) = driver();
+    let mut drdy = FakePin::default();
+    assert!(matches!(
+        l3gd20.gyro_guaranteed_fresh(&mut drdy),
+        Err(Error::Timeout)
+    ));
+    assert_eq!(drdy.polls, DATA_READY_POLLS as usize);
+
+    drdy.broken = true;
+    assert!(matches!(
+        l3gd20.gyro_guaranteed_fresh(&mut drdy),
+        Err(Error::Pin)
+    ));
+}
+
+#[test]
+fn int1_trigger_reports_first_event() {
+    let (mut l3gd20, sensor) = driver();
+    sensor
+        .borrow_mut()
+        .queue(Register::INT1_SRC, &[0x00, 0x3C, 0x50, 0x41, 0x6A]);
+    // Flags without IA are ignored
+    assert_eq!(l3gd20.int1_trigger().unwrap(), None);
+    assert_eq!(l3gd20.int1_trigger().unwrap(), None);
+    assert_eq!(
+        l3gd20.int1_trigger().unwrap(),
+        Some((Axis::Z, Direction::Low))
+    );
+    assert_eq!(
+        l3gd20.int1_trigger().unwrap(),
+        Some((Axis::X, Direction::Low))
+    );
+    assert_eq!(
+        l3gd20.int1_trigger().unwrap(),
+        Some((Axis::X, Direction::High))
+    );
+}
+
+/// Queue the turntable samples of `calibrate_scale_two_point` on the Y axis:
+/// `pos` for the positive rate, a few samples while reversing and `neg`
+/// from then on
+fn turntable(sensor: &Handle, pos: i16, neg: i16) {
+    let mut sensor = sensor.borrow_mut();
+    for _ in 0..SCALE_VERIFICATION_SAMPLES {
+        sensor.push_sample([0, pos, 0]);
+    }
+    sensor.push_sample([0, pos / 2, 0]);
+    sensor.push_sample([0, 0, 0]);
+    sensor.push_sample([0, neg, 0]);
+}
+
+#[test]
+fn calibrate_scale_two_point_uses_span() {
+    let (mut l3gd20, sensor) = driver();
+    // 112 dps measured at +100 dps and -105 dps at -100 dps
+    turntable(&sensor, 12_800, -12_000);
+    let mut delay = NoDelay::default();
+    let correction = l3gd20
+        .calibrate_scale_two_point(Axis::Y, 100.0, -100.0, &mut delay)
+        .unwrap();
+    assert!((correction - 200.0 / 217.0).abs() < 1e-4);
+    assert_eq!(l3gd20.scale_correction(), [1.0, correction, 1.0]);
+
+    // The turntable is never reversed
+    sensor.borrow_mut().set_sample([0, 0, 0]);
+    assert_eq!(
+        l3gd20.calibrate_scale_two_point(Axis::Y, 100.0, -100.0, &mut delay),
+        Err(Error::Timeout)
+    );
+    assert_eq!(l3gd20.scale_correction()[1], correction);
+}
+
+#[test]
+fn calibrate_scale_two_point_ignores_sign_convention() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_sign_convention([false, true, false]);
+    turntable(&sensor, 12_800, -12_000);
+    let correction = l3gd20
+        .calibrate_scale_two_point(Axis::Y, 100.0, -100.0, &mut NoDelay::default())
+        .unwrap();
+    assert!((correction - 200.0 / 217.0).abs() < 1e-4);
+
+    // The corrected rate is inverted once
+    sensor.borrow_mut().set_sample([0, 12_800, 0]);
+    let rate = l3gd20.gyro_for_complementary(0.0).unwrap().rate_dps;
+    assert!((rate.y + 112.0 * correction).abs() < 1e-3);
+}
+
+#[test]
+fn gyro_measurement_variance_follows_bandwidth() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([1000, 0, 0]);
+    let quantization = 0.00875 * 0.00875 / 12.0;
+
+    // 12.5 Hz cut-off at 95 Hz
+    let measurement = l3gd20.gyro_measurement().unwrap();
+    assert!((measurement.rate.x - 8.75).abs() < 1e-4);
+    let expected = 0.03 * 0.03 * 12.5 + quantization;
+    assert!((measurement.variance.x - expected).abs() < 1e-7);
+    assert_eq!(measurement.variance.x, measurement.variance.z);
+
+    // 25 Hz cut-off at 190 Hz
+    sensor.borrow_mut().set(Register::CTRL_REG1, 0x5F);
+    let measurement = l3gd20.gyro_measurement().unwrap();
+    let expected = 0.03 * 0.03 * 25.0 + quantization;
+    assert!((measurement.variance.y - expected).abs() < 1e-7);
+}
+
+#[test]
+fn reference_accessors() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_reference(0xA5).unwrap();
+    assert_eq!(sensor.borrow().last_write(Register::REFERENCE), Some(0xA5));
+    assert_eq!(l3gd20.reference().unwrap(), 0xA5);
+    // Changing the reference changes the output
+    assert!(l3gd20.gyro_settled(&mut NoDelay::default()).is_ok());
+    l3gd20.set_reference(0x00).unwrap();
+    let mut delay = NoDelay::default();
+    l3gd20.gyro_settled(&mut delay).unwrap();
+    assert_ne!(delay.total_ns, 0);
+}
+
+#[test]
+fn decimation_keeps_every_nth_sample() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_decimation(0);
+    assert_eq!(l3gd20.decimation(), 1);
+    assert_eq!(l3gd20.effective_odr().unwrap(), 95.0);
+
+    l3gd20.set_decimation(3);
+    assert!((l3gd20.effective_odr().unwrap() - 95.0 / 3.0).abs() < 1e-4);
+    {
+        let mut sensor = sensor.borrow_mut();
+        for x in 1..=6 {
+            sensor.push_sample([x, 0, 0]);
+        }
+    }
+    assert_eq!(l3gd20.gyro_decimated().unwrap().x, 3);
+    assert_eq!(l3gd20.gyro_decimated().unwrap().x, 6);
+
+    // `gyro` is a single read regardless of the decimation factor
+    sensor.borrow_mut().push_sample([7, 0, 0]);
+    assert_eq!(l3gd20.gyro().unwrap().x, 7);
+    assert!(sensor.borrow().queue_is_empty(Register::OUT_X_L));
+
+    // No new data while powered down
+    l3gd20.power_down().unwrap();
+    sensor.borrow_mut().set(Register::STATUS_REG, 0x00);
+    assert!(matches!(l3gd20.gyro_decimated(), Err(Error::Timeout)));
+    assert_eq!(l3gd20.wait_for_data(), Err(Error::Timeout));
+}
+
+#[test]
+fn measure_recovery_time_counts_from_last_saturation() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        for sample in [
+            [100, 0, 0],
+            [i16::MAX, 0, 0],
+            [0, i16::MIN, 0],
+            [32_000, 0, 0],
+            [0, 0, 500],
+            [10, -10, 0],
+        ] {
+            sensor.push_sample(sample);
+        }
+    }
+    let mut delay = NoDelay::default();
+    // Three periods at 95 Hz until within the noise floor
+    assert_eq!(
+        l3gd20.measure_recovery_time(10, &mut delay).unwrap(),
+        3 * 10_526
+    );
+    assert_eq!(delay.total_ns, 6 * 10_526 * 1000);
+}
+
+#[test]
+fn measure_recovery_time_times_out() {
+    let (mut l3gd20, sensor) = driver();
+    let mut delay = NoDelay::default();
+    // Never saturates
+    assert_eq!(
+        l3gd20.measure_recovery_time(4, &mut delay),
+        Err(Error::Timeout)
+    );
+    assert_eq!(delay.total_ns, 4 * 10_526 * 1000);
+
+    // Stays saturated
+    sensor.borrow_mut().set_sample([i16::MAX, 0, 0]);
+    assert_eq!(
+        l3gd20.measure_recovery_time(4, &mut delay),
+        Err(Error::Timeout)
+    );
+    assert_eq!(delay.total_ns, 9 * 10_526 * 1000);
+}
+
+#[test]
+fn configure_for_noise_density_keeps_power_mode() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_axes_enabled(true, false, true).unwrap();
+    l3gd20.sleep().unwrap();
+    let config = l3gd20.configure_for_noise_density(0.05).unwrap();
+    assert_eq!(config.axes, AXES_X | AXES_Z);
+    assert_eq!(l3gd20.power_mode().unwrap(), PowerMode::Sleep);
+    l3gd20.power_up().unwrap();
+    assert_eq!(l3gd20.axes_enabled(), (true, false, true));
+
+    l3gd20.power_down().unwrap();
+    l3gd20.configure_for_noise_density(0.05).unwrap();
+    assert_eq!(l3gd20.power_mode().unwrap(), PowerMode::PowerDown);
+
+    l3gd20.power_up().unwrap();
+    let config = l3gd20.configure_for_noise_density(0.05).unwrap();
+    assert_eq!(l3gd20.power_mode().unwrap(), PowerMode::Normal);
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1) & 0x07, 0b110);
+    assert!(matches!(l3gd20.scale().unwrap(), Scale::Dps2000));
+    assert!(matches!(config.scale, Scale::Dps2000));
+}
+
+#[test]
+fn mount_matrix_rotates_after_scale_correction() {
+    let (mut l3gd20, sensor) = driver();
+    let m = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+    l3gd20
+        .set_mount_matrix(m)
+        .set_scale_correction([2.0, 1.0, 1.0]);
+    assert_eq!(l3gd20.mount_matrix(), m);
+    // 8.75 dps about the sensor X-axis, doubled by the correction
+    sensor.borrow_mut().set_sample([1000, 0, -1000]);
+    let dps = l3gd20.gyro_dps().unwrap();
+    assert!(dps[0].abs() < 1e-4);
+    assert!((dps[1] - 17.5).abs() < 1e-4);
+    assert!((dps[2] + 8.75).abs() < 1e-4);
+}
+
+#[test]
+fn gyro_dps_uses_cached_scale() {
+    let (mut l3gd20, sensor) = driver();
+    sensor.borrow_mut().set_sample([1000, 0, 0]);
+    let before = sensor.borrow().transactions;
+    assert!((l3gd20.gyro_dps().unwrap()[0] - 8.75).abs() < 1e-4);
+    assert_eq!(sensor.borrow().transactions - before, 1);
+
+    l3gd20.set_scale(Scale::Dps500).unwrap();
+    assert!((l3gd20.gyro_dps().unwrap()[0] - 17.5).abs() < 1e-4);
+    let state = l3gd20.save_state().unwrap();
+    l3gd20.write_register(Register::CTRL_REG4, 0x20).unwrap();
+    assert!((l3gd20.gyro_dps().unwrap()[0] - 70.0).abs() < 1e-4);
+    l3gd20.restore_state(&state).unwrap();
+    assert!((l3gd20.gyro_rad().unwrap()[0] - 17.5f32.to_radians()).abs() < 1e-6);
+}
+
+#[cfg(feature = "libm")]
+#[test]
+fn snr_db_compares_signal_to_window_noise() {
+    let (mut l3gd20, sensor) = driver();
+    let mut window = VarWindow::<2>::new();
+    window.push(I16x3 { x: 0, y: 0, z: 0 });
+    // Standard deviation of 100 LSB, i.e. 0.875 dps, on X and Y
+    sensor.borrow_mut().set_sample([200, -200, 2]);
+    let signal = F32x3 {
+        x: 8.75,
+        y: 0.875,
+        z: 0.875,
+    };
+    let snr = l3gd20.snr_db(signal, &mut window).unwrap();
+    assert!((snr.x - 20.0).abs() < 1e-3);
+    assert!(snr.y.abs() < 1e-3);
+    assert!((snr.z - 40.0).abs() < 1e-3);
+}
+
+#[test]
+fn post_reports_each_check() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::INT1_TSH_XL, 0x42);
+        sensor.push_sample([10, 10, 10]);
+        // 130 dps change at 250 dps full scale
+        sensor.push_sample([14_867, -14_847, 14_867]);
+    }
+    let mut delay = NoDelay::default();
+    let report = l3gd20.post(&mut delay).unwrap();
+    assert!(report.passed());
+    assert_eq!(report.model, Some(Model::L3gd20));
+    assert_eq!(xyz(report.self_test), [14_857, -14_857, 14_857]);
+    assert_eq!(report.noise_variance.x, 0.0);
+    {
+        let sensor = sensor.borrow();
+        assert_eq!(sensor.reg(Register::INT1_TSH_XL), 0x42);
+        assert_eq!(sensor.reg(Register::CTRL_REG4) & 0x06, 0);
+    }
+
+    // An unknown ID and a weak self-test response fail, the rest still runs
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set(Register::WHO_AM_I, 0x00);
+        sensor.push_sample([0, 0, 0]);
+        sensor.push_sample([100, 14_857, 14_857]);
+    }
+    let report = l3gd20.post(&mut delay).unwrap();
+    assert!(!report.passed());
+    assert_eq!(report.model, None);
+    assert!(report.loopback);
+    assert!(!report.self_test_passed);
+    assert!(report.noise_passed);
+}
+
+#[test]
+fn calibrate_averages_new_samples() {
+    let (mut l3gd20, sensor) = driver();
+    {
+        let mut sensor = sensor.borrow_mut();
+        // No new data on the first poll
+        sensor.queue(Register::STATUS_REG, &[0x00, 0x0F]);
+        for sample in [[10, -3, 0], [11, -4, 0], [11, -4, 1], [11, -4, 0]] {
+            sensor.push_sample(sample);
+        }
+    }
+    // Means of 10.75, -3.75 and 0.25 LSB
+    assert_eq!(xyz(l3gd20.calibrate(4).unwrap()), [11, -4, 0]);
+    sensor.borrow_mut().set_sample([20, 20, 20]);
+    assert_eq!(xyz(l3gd20.gyro().unwrap()), [9, 24, 20]);
+}
+
+#[test]
+fn new_with_config_writes_one_burst() {
+    let (spi, sensor) = fake::spi();
+    let config = Config {
+        scale: Scale::Dps2000,
+        block_data_update: true,
+        ..Config::default()
+    };
+    let (mut l3gd20, _) = L3gd20::new_with_config(spi, config).unwrap();
+    {
+        let sensor = sensor.borrow();
+        // WHO_AM_I check and the burst write
+        assert_eq!(sensor.transactions, 2);
+        assert_eq!(sensor.writes.len(), 5);
+        assert_eq!(sensor.reg(Register::CTRL_REG4), 0xB0);
+    }
+    assert!(matches!(l3gd20.scale().unwrap(), Scale::Dps2000));
+}
+
+#[test]
+fn all_scaled_converts_rate_and_temperature() {
+    let (mut l3gd20, sensor) = driver();
+    l3gd20.set_scale(Scale::Dps500).unwrap();
+    {
+        let mut sensor = sensor.borrow_mut();
+        sensor.set_sample([1000, 0, -1000]);
+        sensor.set(Register::OUT_TEMP, (-5i8) as u8);
+    }
+    let before = sensor.borrow().transactions;
+    let scaled = l3gd20.all_scaled().unwrap();
+    assert_eq!(sensor.borrow().transactions - before, 1);
+    assert!((scaled.gyro[0] - 17.5).abs() < 1e-4);
+    assert_eq!(scaled.gyro[1], 0.0);
+    assert!((scaled.gyro[2] + 17.5).abs() < 1e-4);
+    assert_eq!(scaled.temp_c, TEMP_OFFSET_DEFAULT + 5);
+}
+
+#[test]
+fn temp_offset_applies_to_all_conversions() {
+    let (mut l3gd20, sensor) = driver();
+    assert_eq!(l3gd20.temp_offset(), TEMP_OFFSET_DEFAULT);
+    sensor.borrow_mut().set(Register::OUT_TEMP, 3);
+    assert_eq!(l3gd20.temp_celcius().unwrap(), TEMP_OFFSET_DEFAULT - 3);
+
+    l3gd20.set_temp_offset(40);
+    assert_eq!(l3gd20.temp_celcius().unwrap(), 37);
+    assert_eq!(l3gd20.all_scaled().unwrap().temp_c, 37);
+    assert_eq!(l3gd20.all().unwrap().temp_celcius_with_offset(40), 37);
+}
+
+#[test]
+fn low_odr_only_on_l3gd20h() {
+    let (mut l3gd20, sensor) = driver();
+    assert!(matches!(
+        l3gd20.set_low_odr(LowOdr::Hz25),
+        Err(Error::Unsupported)
+    ));
+    assert_eq!(l3gd20.low_odr().unwrap(), None);
+    l3gd20.clear_low_odr().unwrap();
+    assert_eq!(sensor.borrow().write_count(Register::LOW_ODR), 0);
+
+    sensor
+        .borrow_mut()
+        .set(Register::WHO_AM_I, WHO_AM_I_L3GD20H);
+    assert_eq!(l3gd20.low_odr().unwrap(), None);
+    l3gd20.set_low_odr(LowOdr::Hz25).unwrap();
+    assert_eq!(sensor.borrow().reg(Register::CTRL_REG1), 0x4F);
+    assert_eq!(sensor.borrow().reg(Register::LOW_ODR), 0x01);
+    assert_eq!(l3gd20.low_odr().unwrap(), Some(LowOdr::Hz25));
+
+    l3gd20.clear_low_odr().unwrap();
+    assert_eq!(sensor.borrow().reg(Register::LOW_ODR), 0x00);
+    assert_eq!(l3gd20.low_odr().unwrap(), None);
+}
@@ -0,0 +1,251 @@
+//! Tests of the bus independent types
+
+use super::xyz;
+use crate::*;
+
+#[test]
+fn var_window_replaces_oldest_sample() {
+    let mut window = VarWindow::<3>::new();
+    assert!(window.is_empty());
+    assert_eq!(window.variance().x, 0.0);
+    for x in [100, 1, 2, 3] {
+        window.push(I16x3 { x, y: 7, z: -x });
+    }
+    assert_eq!(window.len(), 3);
+    let mean = window.mean();
+    assert_eq!((mean.x, mean.y, mean.z), (2.0, 7.0, -2.0));
+    let var = window.variance();
+    assert!((var.x - 2.0 / 3.0).abs() < 1e-6);
+    assert_eq!(var.y, 0.0);
+    assert!((var.z - 2.0 / 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn registers_decode_full_image() {
+    let image = [
+        0xDF, 0x10, 0x08, 0xB0, 0x40, // CTRL_REG1 to CTRL_REG5
+        0x55, 0xF6, 0x88, // REFERENCE, OUT_TEMP, STATUS_REG
+        0x34, 0x12, 0xFF, 0xFF, 0x00, 0x80, // OUT_X to OUT_Z
+        0x4A, 0x8A, 0x7F, 0x41, // FIFO_CTRL_REG, FIFO_SRC_REG, INT1_CFG, INT1_SRC
+        0x81, 0x02, 0x7F, 0xFF, 0x00, 0x05, // INT1_TSH_XH to INT1_TSH_ZL
+        0x85, // INT1_DURATION
+    ];
+    let regs = Registers::decode(&image);
+    assert_eq!(regs.ctrl, [0xDF, 0x10, 0x08, 0xB0, 0x40]);
+    assert!(matches!(regs.odr, Odr::Hz760));
+    assert!(matches!(regs.bandwidth, Bandwidth::Medium));
+    assert!(matches!(regs.scale, Scale::Dps2000));
+    assert!(regs.bdu);
+    assert_eq!(regs.reference, Some(0x55));
+    assert!(regs.status.overrun && regs.status.new_data && !regs.status.x_new);
+    let gyro = regs.measurements.gyro;
+    assert_eq!([gyro.x, gyro.y, gyro.z], [0x1234, -1, i16::MIN]);
+    assert_eq!(regs.measurements.temp_raw, -10);
+    assert_eq!(regs.fifo_mode, FifoMode::Stream);
+    assert_eq!(regs.fifo_watermark, 0x0A);
+    let fifo = regs.fifo_status;
+    assert!(fifo.watermark && !fifo.overrun && !fifo.empty && fifo.stored == 0x0A);
+    assert_eq!(regs.int1_config.high, [true; 3]);
+    assert_eq!(regs.int1_config.low, [true; 3]);
+    assert!(regs.int1_config.latch && !regs.int1_config.and_events);
+    assert!(regs.int1_source.active && regs.int1_source.x_low && !regs.int1_source.x_high);
+    assert_eq!(regs.int1_threshold, [0x0102, 0x7FFF, 0x0005]);
+    assert_eq!((regs.int1_duration, regs.int1_wait), (0x05, true));
+
+    // Not read in NormalReset mode
+    let mut image = image;
+    image[1] = 0x00;
+    assert_eq!(Registers::decode(&image).reference, None);
+}
+
+#[test]
+fn scale_to_raw_rounds_and_saturates() {
+    assert_eq!(Scale::Dps250.to_raw(100.0), 11429);
+    assert_eq!(Scale::Dps250.to_raw(-100.0), -11429);
+    assert_eq!(Scale::Dps500.to_raw(0.0175 * 1.4), 1);
+    assert_eq!(Scale::Dps2000.to_raw(-0.07 * 2.5), -3);
+    for scale in [Scale::Dps250, Scale::Dps500, Scale::Dps2000] {
+        assert_eq!(scale.to_raw(scale.degrees(1234)), 1234);
+        assert_eq!(scale.to_raw(1e6), i16::MAX);
+        assert_eq!(scale.to_raw(-1e6), i16::MIN);
+    }
+}
+
+#[test]
+fn gyro_sample_c_layout() {
+    use core::mem::{align_of, offset_of, size_of};
+
+    assert_eq!(size_of::<GyroSampleC>(), 8);
+    assert_eq!(align_of::<GyroSampleC>(), 2);
+    assert_eq!(offset_of!(GyroSampleC, x), 0);
+    assert_eq!(offset_of!(GyroSampleC, y), 2);
+    assert_eq!(offset_of!(GyroSampleC, z), 4);
+    assert_eq!(offset_of!(GyroSampleC, temp), 6);
+    assert_eq!(offset_of!(GyroSampleC, status), 7);
+}
+
+#[test]
+fn scale_radians_matches_manual_conversion() {
+    for scale in [Scale::Dps250, Scale::Dps500, Scale::Dps2000] {
+        for val in [i16::MIN, -1234, -1, 0, 1, 5000, i16::MAX] {
+            let manual = scale.degrees(val) * (core::f32::consts::PI / 180.0);
+            // Same result with any combination of features
+            assert_eq!(scale.radians(val).to_bits(), manual.to_bits());
+        }
+    }
+    // 32767 LSB are 2293.69 dps at 2000 dps
+    assert!((Scale::Dps2000.radians(i16::MAX) - 40.033).abs() < 0.001);
+}
+
+#[test]
+fn scale_rescale_rounds_to_target_resolution() {
+    // 8.75 mdps/LSB to 100 LSB/dps
+    assert_eq!(Scale::Dps250.rescale(1, 100), 1);
+    assert_eq!(Scale::Dps250.rescale(-1, 100), -1);
+    assert_eq!(Scale::Dps250.rescale(1143, 100), 1000);
+    assert_eq!(Scale::Dps2000.rescale(i16::MIN, 1000), -2_293_760);
+    assert_eq!(Scale::Dps2000.rescale(i16::MAX, u16::MAX), 150_316_974);
+}
+
+#[test]
+fn bias_keeps_fraction() {
+    // Mean of 1 and 2 is 1.5 LSB
+    let bias = Bias::from_sums([3, -3, 0], 2);
+    assert_eq!(xyz(bias.to_i16x3()), [2, -1, 0]);
+    let raw = I16x3 {
+        x: 10,
+        y: 10,
+        z: 10,
+    };
+    assert_eq!(xyz(bias.apply(raw)), [9, 12, 10]);
+
+    let mut bias = Bias::from_i16x3(I16x3 { x: 0, y: 0, z: 0 });
+    for _ in 0..1000 {
+        bias.track(
+            I16x3 {
+                x: 5,
+                y: -5,
+                z: i16::MAX,
+            },
+            BIAS_TRACKING_SHIFT,
+        );
+    }
+    assert_eq!(xyz(bias.to_i16x3()), [5, -5, i16::MAX]);
+}
+
+#[test]
+fn allan_accumulator_clusters() {
+    let mut acc = AllanAccumulator::<2>::new();
+    acc.push(I16x3 { x: 0, y: 5, z: 0 });
+    assert!(acc.variance(1).is_none());
+    for i in 1..8 {
+        acc.push(I16x3 {
+            x: (i % 2) * 2,
+            y: 5,
+            z: 0,
+        });
+    }
+    assert_eq!(acc.clusters(1), 8);
+    assert_eq!(acc.clusters(2), 4);
+    let var = acc.variance(1).unwrap();
+    assert_eq!((var.x, var.y), (2.0, 0.0));
+    // Alternating samples average out over clusters of two
+    assert_eq!(acc.variance(2).unwrap().x, 0.0);
+    assert!(acc.variance(3).is_none());
+    assert!(acc.variance(4).is_none());
+    assert_eq!(acc.clusters(4), 0);
+}
+
+#[cfg(feature = "libm")]
+#[test]
+fn rotation_update_quarter_turn() {
+    let close = |a: [[f32; 3]; 3], b: [[f32; 3]; 3]| {
+        a.iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .all(|(a, b)| (a - b).abs() < 1e-4)
+    };
+    let still = I16x3 { x: 0, y: 0, z: 0 };
+    let identity = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    assert!(close(
+        still.to_rotation_update(Scale::Dps250, 1.0),
+        identity
+    ));
+
+    // 87.5 dps about Z for 90 degrees
+    let rate = I16x3 {
+        x: 0,
+        y: 0,
+        z: 10_000,
+    };
+    let rotation = rate.to_rotation_update(Scale::Dps250, 90.0 / 87.5);
+    let quarter = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]];
+    assert!(close(rotation, quarter));
+}
+
+#[test]
+fn scale_triples_keep_axis_order() {
+    let v = I16x3 {
+        x: 1000,
+        y: -2000,
+        z: 0,
+    };
+    let dps = Scale::Dps500.degrees_triple(v);
+    assert!((dps[0] - 17.5).abs() < 1e-4);
+    assert!((dps[1] + 35.0).abs() < 1e-4);
+    assert_eq!(dps[2], 0.0);
+
+    let rad = Scale::Dps500.radians_triple(v);
+    for (rad, dps) in rad.iter().zip(dps) {
+        assert!((rad - dps.to_radians()).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn config_encodes_control_registers() {
+    let config = Config {
+        odr: Odr::Hz380,
+        bandwidth: Bandwidth::High,
+        scale: Scale::Dps500,
+        axes: AXES_Y,
+        block_data_update: true,
+        high_pass: true,
+        high_pass_mode: HighPassMode::Reference,
+        high_pass_cutoff: HighPassCutoff::new(3).unwrap(),
+        out_select: OutSelect::HighPass,
+    };
+    let regs = config.registers();
+    assert_eq!(regs, [0xA9, 0x13, 0x00, 0x90, 0x11]);
+
+    let decoded = Config::from_registers(&regs);
+    assert!(matches!(decoded.odr, Odr::Hz380));
+    assert!(matches!(decoded.bandwidth, Bandwidth::High));
+    assert!(matches!(decoded.scale, Scale::Dps500));
+    assert_eq!(decoded.axes, AXES_Y);
+    assert!(decoded.block_data_update && decoded.high_pass);
+    assert_eq!(decoded.high_pass_mode, HighPassMode::Reference);
+    assert_eq!(decoded.high_pass_cutoff, HighPassCutoff::new(3).unwrap());
+    assert!(matches!(decoded.out_select, OutSelect::HighPass));
+    assert_eq!(Config::default().registers(), [0x0F, 0, 0, 0, 0]);
+}
+
+#[test]
+fn config_clamps_to_effective_settings() {
+    let config = Config {
+        bandwidth: Bandwidth::Maximum,
+        axes: 0xF8 | AXES_Z,
+        ..Config::default()
+    }
+    .clamped();
+    // Same 25 Hz cut-off at 95 Hz
+    assert!(matches!(config.bandwidth, Bandwidth::Medium));
+    assert_eq!(config.axes, AXES_Z);
+
+    let config = Config {
+        odr: Odr::Hz760,
+        bandwidth: Bandwidth::Maximum,
+        ..Config::default()
+    }
+    .clamped();
+    assert!(matches!(config.bandwidth, Bandwidth::Maximum));
+}